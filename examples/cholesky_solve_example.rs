@@ -37,6 +37,11 @@ fn main() {
         } else {
             println!("Cholesky factorization failed.")
         }
+        println!("\nSolution with iterative refinement, cholesky_solve_refined:");
+        match cholesky_solve_refined(&A, &b, 0f64, 5) {
+            Ok(report) => println!("{}", report),
+            Err(e) => println!("{}", e)
+        }
         println!("\nSolution with no regularization or Ruiz equilibration\n via Cholesky \
                  factorization, forward_solve and back_solve:");
         let chol_A = A.clone().cholesky().unwrap();
@@ -1,4 +1,4 @@
-use convopt::optimization::golden_search;
+use convopt::optimization::{golden_search, LineSearch, GoldenSearch, BrentSearch};
 
 fn main() {
 
@@ -23,4 +23,19 @@ fn main() {
              a = {0:.4}, f(a) = {1:.4}\n",res.0,res.1
     );
 
-}
\ No newline at end of file
+    // accuracy versus number of function evaluations
+    let f = |t:f64| (t-0.3f64).exp()-t;   // minimizer is t=0.3
+    let eps = 1e-8;
+    let line_searches: Vec<Box<dyn LineSearch>> = vec![
+        Box::new(GoldenSearch::new(eps)),
+        Box::new(BrentSearch::new(eps,100))
+    ];
+    println!("\nMinimizing f(t)=exp(t-0.3)-t on [0,1], minimizer is t=0.3:");
+    for ls in line_searches.iter() {
+
+        let res = ls.minimize(&f,0f64,1f64);
+        println!("{0}: t = {1:.10}, f(t) = {2:.10}, evaluations: {3}, converged: {4}",
+                 ls.id(),res.t,res.f_t,res.evaluations,res.converged
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use convopt::{
     DVec,
-    optimization::{solve_min_problem},
+    optimization::{solve_min_problem, GoldenSearch},
     test_problems::Maxent
 };
 
@@ -9,8 +9,9 @@ fn main() {
     let min_prob = Maxent::new(dim);
     let eps = 1e-4;
     let max_iter = 100usize;
+    let line_search = GoldenSearch::new(0.1);
     println!("\nSolving problem Maxent in dimension {}", dim);
-    match solve_min_problem(&min_prob, eps, max_iter) {
+    match solve_min_problem(&min_prob, &line_search, eps, max_iter) {
         Ok(x) => println!("Solution:\n{}", x),
         Err(e) => println!("Error: {}", e)
     }
@@ -1,6 +1,6 @@
 use convopt::{
     DVec,
-    optimization::{solve_min_problem},
+    optimization::{solve_min_problem, GoldenSearch},
     test_problems::Rosenbrook
 };
 
@@ -9,8 +9,9 @@ fn main() {
     let min_prob = Rosenbrook::new(1f64,10f64);
     let eps = 1e-4;
     let max_iter = 100usize;
+    let line_search = GoldenSearch::new(0.1);
     println!("\nSolving problem Rosenbrook, minimum at (x,y)=(1,-1):");
-    match solve_min_problem(&min_prob, eps, max_iter) {
+    match solve_min_problem(&min_prob, &line_search, eps, max_iter) {
         Ok(x) => println!("Solution:\n{}", x),
         Err(e) => println!("Error: {}", e)
     }
@@ -1,19 +1,44 @@
 
 use std::io::{stdout, Write};
 use convopt::{
-    DVec,
+    DVec, DMat,
     matrix_utils::*,
     logging::Logger
 };
-use nalgebra::SymmetricEigen;
-use lazysort::Sorted;
+use rand::Rng;
 use rand_xoshiro::{Xoshiro256PlusPlus, rand_core::SeedableRng};
 
-/// We allocate 200 random symmetric positive definite 500 x 500 matrices A with
-/// condition number = 1e6,
-/// apply the Ruiz equilibration algorithm and compute the condition number of the equilibrated
-/// matrix B. The ratios cond(B) / cond(A) (hopefully << 1) are kept and a histogram of same
-/// is printed, as well as some statistics written to a text file.
+
+/// Writes the 10%, 50% and 90% quantiles of the values v.
+fn write_quantiles(logger: &mut Logger, v: &mut Vec<f64>) {
+
+    let n = v.len();
+    v.sort_by(|a:&f64,b:&f64| a.partial_cmp(b).unwrap());
+    logger.write(format!(
+        "quantile_10%: {:.3e}, quantile_50%: {:.3e}, quantile_90%: {:.3e}\n",
+        v[n/10],v[n/2],v[9*n/10]
+    ).as_str());
+}
+
+
+/// The matrix SAS with random diagonal S = diag(10^u), u uniform in [-3,3].
+fn badly_scaled(A: &DMat, rng: &mut impl Rng) -> DMat {
+
+    let n = A.nrows();
+    let s = DVec::from_fn(n,|_,_| 10f64.powf(rng.gen_range(-3f64..3f64)));
+    DMat::from_fn(n,n,|r,c| s[r]*s[c]*A[(r,c)])
+}
+
+
+/// We allocate random symmetric positive definite 200 x 200 matrices A with
+/// condition number = 1e6 (and badly scaled versions of them),
+/// apply each equilibration strategy and compute the condition number of the equilibrated
+/// matrix B. The ratios cond(B) / cond(A) (hopefully << 1) are kept and some statistics
+/// are written to a text file for each strategy and matrix family.
+///
+/// The 1-norm condition numbers are estimated from the Cholesky factors (Hager/Higham), the
+/// 2-norm condition numbers by 30 Lanczos steps, both are much cheaper than the eigen
+/// decomposition.
 ///
 fn main() {
 
@@ -21,39 +46,58 @@ fn main() {
 
     let l_max = 1000f64;    // largest eigenvalue
     let l_min = 0.001f64;   // smallest eigenvalue
-    let cond_A = l_max/l_min;
-    let n = 200;
+    let n = 100;
     let dim = 200usize;
-    let mut cond_quotient: Vec<f64> =  Vec::with_capacity(n);
+
+    let strategies: Vec<Box<dyn Equilibration>> = vec![
+        Box::new(Ruiz::new(5,5)),
+        Box::new(RuizConvergent::new(1e-3,100)),
+        Box::new(JacobiScaling{}),
+        Box::new(SinkhornKnopp::new(1e-3,100)),
+        Box::new(GeometricMean::new(5)),
+    ];
+    let families = ["random psd", "badly scaled random psd"];
 
     let mut logger = Logger::new("results/ruiz_conditioning.txt");
     logger.write(format!(
-        "\nStatistics for cond(ruiz_equilibrated(A))/cond(A) for {} {}x{} matrices:\n\n",
+        "\nStatistics for cond(equilibrated(A))/cond(A) for {} {}x{} matrices per family:\n\n",
         n,dim,dim
     ).as_str());
 
-
-    println!("\nComputing condition numbers of {} {}x{} matrices",n,dim,dim);
+    println!("\nComputing condition numbers of {} {}x{} matrices",2*n,dim,dim);
     println!("this can take a while (20 *): ");
 
-    let n_oo: usize = 5;   // number of rounds of ||.||_oo equilibration
-    let n_2: usize = 5;    // number of rounds of ||.||_2 equilibration
+    // cond_quotient[family][strategy], cond2_quotient[family][strategy]
+    let mut cond_quotient = vec![vec![Vec::with_capacity(n); strategies.len()]; families.len()];
+    let mut cond2_quotient = vec![vec![Vec::with_capacity(n); strategies.len()]; families.len()];
 
     for k in 0..n {
 
-        if k%(n/20)==0 { print!("*"); stdout().flush(); }
-        let A = random_psd_matrix(dim,l_max,l_min,&mut rng);
-        let B = ruiz_equilibration(&A,n_oo,n_2).1;
+        if k%(n/20)==0 { print!("*"); stdout().flush().unwrap(); }
+        let A0 = random_psd_matrix(dim,l_min,l_max,&mut rng);
+        let A1 = badly_scaled(&A0,&mut rng);
+        for (f,A) in [A0,A1].iter().enumerate() {
+
+            let cond_A = cholesky_condition_estimate(A,&A.clone().cholesky().unwrap());
+            let cond2_A = lanczos_condition_estimate(A,30);
+            for (s,eq) in strategies.iter().enumerate() {
+
+                let B = eq.equilibrate(A).1;
+                let cond_B = cholesky_condition_estimate(&B,&B.clone().cholesky().unwrap());
+                cond_quotient[f][s].push(cond_B/cond_A);
+                cond2_quotient[f][s].push(lanczos_condition_estimate(&B,30)/cond2_A);
+            }
+        }
+    }
+    for (f,family) in families.iter().enumerate() {
+        for (s,eq) in strategies.iter().enumerate() {
 
-        let eig_B = SymmetricEigen::new(B);
-        let eig_vals_B = eig_B.eigenvalues;
-        let cond_B = eig_vals_B.amax()/eig_vals_B.amin();
-        cond_quotient.push(cond_B/cond_A);
+            logger.write(format!("\n{}, {}:\n",family,eq.id()).as_str());
+            logger.write("cond_1(B)/cond_1(A) (Hager/Higham): ");
+            write_quantiles(&mut logger,&mut cond_quotient[f][s]);
+            logger.write("cond_2(B)/cond_2(A) (Lanczos):      ");
+            write_quantiles(&mut logger,&mut cond2_quotient[f][s]);
+        }
     }
-    cond_quotient.sort_by(|a:&f64,b:&f64| a.partial_cmp(b).unwrap());
-    logger.write("\nStatistics for cond(ruiz_equilibrated(A))/cond(A):\n");
-    logger.write(format!("quantile_10%: {}\n",cond_quotient[n/10]).as_str());
-    logger.write(format!("quantile_50%: {}\n",cond_quotient[n/2]).as_str());
-    logger.write(format!("quantile_90%: {}\n",cond_quotient[9*n/10]).as_str());
     println!("\nFinished, results in results/ruiz_conditioning.txt")
 }
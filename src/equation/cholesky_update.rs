@@ -0,0 +1,64 @@
+use crate::{
+    Result, DVector, DMatrix, Real,
+    error::*
+};
+
+
+
+/// Rank-one update (sign = 1) or downdate (sign = -1) of a Cholesky factor: overwrites the
+/// lower triangular factor L of $A=LL'$ with the factor of $A+sign*vv'$ in O(n²) by a sequence
+/// of Givens (update) or hyperbolic (downdate) rotations. Only the lower triangle of L is used.
+///
+/// A downdate fails if $A-vv'$ is not (numerically) positive definite, L is then left in an
+/// unspecified state.
+///
+/// # Arguments
+///
+/// * `L`: lower triangular Cholesky factor with positive diagonal
+/// * `v`: vector of same dimension as L
+/// * `sign`: 1 for update, -1 for downdate
+///
+pub fn cholesky_update_in_place<T: Real>(L: &mut DMatrix<T>, v: &DVector<T>, sign: T) -> Result<()> {
+
+    let n = L.nrows();
+    assert!(L.ncols()==n && v.len()==n);
+    assert!(sign==T::one() || sign==-T::one(),"sign must be 1 or -1, got {}",sign);
+
+    let mut x = v.clone();
+    for k in 0..n {
+
+        let l_kk = L[(k,k)];
+        let r2 = l_kk*l_kk+sign*x[k]*x[k];
+        if r2 <= T::zero() || !r2.is_finite() || l_kk == T::zero() {
+            return Err(ConvOptError::new(
+                ErrKind::CholeskyFailure("in cholesky_update: result not positive definite")
+            ));
+        }
+        let r = r2.sqrt();
+        let c = r/l_kk;
+        let s = x[k]/l_kk;
+        L[(k,k)] = r;
+        for i in k+1..n {
+            L[(i,k)] = (L[(i,k)]+sign*s*x[i])/c;
+            x[i] = c*x[i]-s*L[(i,k)];
+        }
+    }
+    Ok(())
+}
+
+
+/// The Cholesky factor of $LL'+sign*vv'$ computed from the Cholesky factor L in O(n²),
+/// see cholesky_update_in_place.
+///
+/// # Arguments
+///
+/// * `L`: lower triangular Cholesky factor with positive diagonal
+/// * `v`: vector of same dimension as L
+/// * `sign`: 1 for update, -1 for downdate
+///
+pub fn cholesky_update<T: Real>(L: &DMatrix<T>, v: &DVector<T>, sign: T) -> Result<DMatrix<T>> {
+
+    let mut M = L.lower_triangle();
+    cholesky_update_in_place(&mut M,v,sign)?;
+    Ok(M)
+}
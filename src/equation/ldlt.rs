@@ -0,0 +1,284 @@
+use std::fmt;
+use crate::{
+    Result, DVec, DMat,
+    error::*
+};
+use super::equilibrated_regularized;
+
+
+
+/// Inertia of a symmetric matrix: the numbers of positive, negative and zero eigenvalues.
+///
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Inertia {
+    pub positive: usize,
+    pub negative: usize,
+    pub zero: usize,
+}
+
+impl fmt::Display for Inertia {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!("(+{}, -{}, 0:{})",self.positive,self.negative,self.zero).as_str())
+    }
+}
+
+
+/// Bunch-Kaufman factorization PAP' = LDL' of a symmetric (possibly indefinite) matrix A,
+/// where P is a permutation, L is unit lower triangular and D is block diagonal with 1x1 and
+/// 2x2 blocks. Pivoting is symmetric, so the factorization exploits the symmetry of A
+/// (n³/3 flops versus 4n³/3 for QR) and D has the same inertia as A (Sylvester).
+///
+#[derive(Clone, Debug)]
+pub struct LDLT {
+    /// unit lower triangular factor
+    pub L: DMat,
+    /// block diagonal factor
+    pub D: DMat,
+    /// sizes (1 or 2) of the diagonal blocks of D, in order
+    pub blocks: Vec<usize>,
+    /// row k of PAP' is row perm[k] of A
+    pub perm: Vec<usize>,
+}
+
+impl LDLT {
+
+    /// Bunch-Kaufman factorization of the symmetric matrix A (only the lower triangle is used).
+    pub fn new(A: &DMat) -> LDLT {
+
+        let n = A.nrows();
+        assert!(A.ncols()==n,"matrix A not square, rows={}, cols={}",n,A.ncols());
+
+        // work on a full symmetric copy, the columns < k hold the computed columns of L
+        let mut W = DMat::from_fn(n,n,|i,j| if i >= j { A[(i,j)] } else { A[(j,i)] });
+        let mut perm: Vec<usize> = (0..n).collect();
+        let mut blocks: Vec<usize> = Vec::with_capacity(n);
+        let alpha = (1f64+17f64.sqrt())/8f64;
+
+        let mut k = 0;
+        while k < n {
+
+            let abs_akk = W[(k,k)].abs();
+            let (imax,colmax) = (k+1..n).map(|i| (i,W[(i,k)].abs())).
+                fold((k,0f64),|a,b| if b.1 > a.1 { b } else { a });
+
+            let (kp,size) =
+                if abs_akk.max(colmax) == 0f64 || abs_akk >= alpha*colmax { (k,1) } else {
+
+                    let rowmax = (k..n).filter(|&j| j != imax).
+                        map(|j| W[(imax,j)].abs()).fold(0f64,f64::max);
+                    if abs_akk*rowmax >= alpha*colmax*colmax { (k,1) }
+                    else if W[(imax,imax)].abs() >= alpha*rowmax { (imax,1) }
+                    else { (imax,2) }
+                };
+
+            // symmetric interchange of rows/columns kk and kp
+            let kk = k+size-1;
+            if kp != kk {
+                W.swap_rows(kk,kp);
+                W.swap_columns(kk,kp);
+                perm.swap(kk,kp);
+            }
+
+            if size == 1 {
+
+                let d = W[(k,k)];
+                if d != 0f64 {
+                    for i in k+1..n { W[(i,k)] /= d; }
+                    for j in k+1..n {
+                        let w_jk = d*W[(j,k)];
+                        for i in j..n { W[(i,j)] -= W[(i,k)]*w_jk; W[(j,i)] = W[(i,j)]; }
+                    }
+                }
+            } else {
+
+                let (a,b,c) = (W[(k,k)],W[(k+1,k)],W[(k+1,k+1)]);
+                let det = a*c-b*b;
+                for i in k+2..n {
+
+                    // (l_ik, l_i,k+1) = (w_ik, w_i,k+1) D^{-1}
+                    let (u,v) = (W[(i,k)],W[(i,k+1)]);
+                    W[(i,k)] = (c*u-b*v)/det;
+                    W[(i,k+1)] = (a*v-b*u)/det;
+                }
+                for j in k+2..n {
+                    // w_j = D (l_jk, l_j,k+1)'
+                    let (l0,l1) = (W[(j,k)],W[(j,k+1)]);
+                    let (w0,w1) = (a*l0+b*l1,b*l0+c*l1);
+                    for i in j..n {
+                        W[(i,j)] -= W[(i,k)]*w0+W[(i,k+1)]*w1;
+                        W[(j,i)] = W[(i,j)];
+                    }
+                }
+            }
+            blocks.push(size);
+            k += size;
+        }
+
+        // extract L and D
+        let mut L = DMat::identity(n,n);
+        let mut D = DMat::repeat(n,n,0f64);
+        let mut k = 0;
+        for &size in blocks.iter() {
+
+            for j in k..k+size {
+                for i in k+size..n { L[(i,j)] = W[(i,j)]; }
+            }
+            D[(k,k)] = W[(k,k)];
+            if size == 2 {
+                D[(k+1,k)] = W[(k+1,k)];
+                D[(k,k+1)] = W[(k+1,k)];
+                D[(k+1,k+1)] = W[(k+1,k+1)];
+            }
+            k += size;
+        }
+        LDLT{ L, D, blocks, perm }
+    }
+
+    /// Inertia of A computed from the blocks of D, eigenvalues with absolute value
+    /// at most tol*max|D_ij| are counted as zero.
+    pub fn inertia(&self, tol: f64) -> Inertia {
+
+        let eps = tol*self.D.amax();
+        let mut inertia = Inertia{ positive: 0, negative: 0, zero: 0 };
+        let mut count = |ev: f64| {
+            if ev > eps { inertia.positive += 1 } else if ev < -eps { inertia.negative += 1 }
+            else { inertia.zero += 1 }
+        };
+        let mut k = 0;
+        for &size in self.blocks.iter() {
+
+            if size == 1 { count(self.D[(k,k)]); } else {
+
+                // eigenvalues of the 2x2 block
+                let (a,b,c) = (self.D[(k,k)],self.D[(k+1,k)],self.D[(k+1,k+1)]);
+                let m = 0.5f64*(a+c);
+                let r = (0.25f64*(a-c)*(a-c)+b*b).sqrt();
+                count(m+r);
+                count(m-r);
+            }
+            k += size;
+        }
+        inertia
+    }
+
+    /// Solves Ax=b. Fails if D has a singular block.
+    pub fn solve(&self, b: &DVec) -> Result<DVec> {
+
+        let n = self.perm.len();
+        assert!(b.len()==n);
+        let y = DVec::from_fn(n,|k,_| b[self.perm[k]]);
+        let mut z = forward_solve_unit(&self.L,&y);
+
+        // Dw=z
+        let norm_D = self.D.amax();
+        let mut k = 0;
+        for &size in self.blocks.iter() {
+
+            if size == 1 {
+                let d = self.D[(k,k)];
+                if d.abs() <= 1e-300f64.max(1e-20*norm_D) {
+                    return Err(ConvOptError::new(ErrKind::LDLTFailure("singular 1x1 pivot")));
+                }
+                z[k] /= d;
+            } else {
+                let (a,b,c) = (self.D[(k,k)],self.D[(k+1,k)],self.D[(k+1,k+1)]);
+                let det = a*c-b*b;
+                if det.abs() <= 1e-300f64.max(1e-40*norm_D*norm_D) {
+                    return Err(ConvOptError::new(ErrKind::LDLTFailure("singular 2x2 pivot")));
+                }
+                let (u,v) = (z[k],z[k+1]);
+                z[k] = (c*u-b*v)/det;
+                z[k+1] = (a*v-b*u)/det;
+            }
+            k += size;
+        }
+        let w = back_solve_unit_transposed(&self.L,&z);
+        let mut x = DVec::repeat(n,0f64);
+        for k in 0..n { x[self.perm[k]] = w[k]; }
+        Ok(x)
+    }
+}
+
+
+/// Solves Lx=y for unit lower triangular L.
+fn forward_solve_unit(L: &DMat, y: &DVec) -> DVec {
+
+    let n = y.len();
+    let mut x = y.clone();
+    for j in 0..n {
+        let x_j = x[j];
+        for i in j+1..n { x[i] -= L[(i,j)]*x_j; }
+    }
+    x
+}
+
+
+/// Solves L'x=y for unit lower triangular L.
+fn back_solve_unit_transposed(L: &DMat, y: &DVec) -> DVec {
+
+    let n = y.len();
+    let mut x = y.clone();
+    for j in (0..n).rev() {
+        let mut s = x[j];
+        for i in j+1..n { s -= L[(i,j)]*x[i]; }
+        x[j] = s;
+    }
+    x
+}
+
+
+
+/// Solve the regularized equation $(H+l*I)x=b$ for a symmetric, possibly indefinite matrix H
+/// by Bunch-Kaufman LDL' factorization of $H+l*I$. Uses Ruiz preconditioning of $H$, the
+/// regularization is applied to the equilibrated matrix as in cholesky_solve_regularized.
+///
+/// Returns the solution and the inertia of $H+l*I$ (equilibration does not change the
+/// inertia).
+///
+/// # Arguments
+///
+/// * `H`: symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn ldlt_solve(H: &DMat, b: &DVec, l: f64) -> Result<(DVec,Inertia)> {
+
+    let n = H.shape().0;  // number of rows
+    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+
+    let (d,G) = equilibrated_regularized(H,l);
+
+    let ldlt = LDLT::new(&G);
+    let c = DVec::from_fn(n,|i,_| b[i]*d[i]);
+    let u = ldlt.solve(&c)?;
+    Ok((DVec::from_fn(n,|i,_| u[i]*d[i]), ldlt.inertia(1e-12)))
+}
+
+
+/// Solves the KKT system
+///     [H A'] [x]   [g]
+///     [A 0 ] [y] = [h]
+/// of an equality constrained quadratic problem by LDL' factorization of the
+/// (symmetric indefinite) KKT matrix with Ruiz preconditioning.
+///
+/// Returns (x,y,inertia of the KKT matrix). If A (m x n) has full row rank, the Hessian H
+/// is positive definite on the null space of A (the reduced Hessian is positive definite)
+/// if and only if the inertia is (n,m,0). Otherwise x is not a minimizer of the quadratic
+/// problem and the caller should regularize H.
+///
+pub fn kkt_solve(H: &DMat, A: &DMat, g: &DVec, h: &DVec) -> Result<(DVec,DVec,Inertia)> {
+
+    let n = H.nrows();
+    let m = A.nrows();
+    assert!(H.ncols()==n && A.ncols()==n && g.len()==n && h.len()==m);
+
+    let K = DMat::from_fn(n+m,n+m,|i,j|
+        if i < n && j < n { H[(i,j)] } else if i >= n && j < n { A[(i-n,j)] }
+        else if i < n && j >= n { A[(j-n,i)] } else { 0f64 }
+    );
+    let rhs = DVec::from_fn(n+m,|i,_| if i < n { g[i] } else { h[i-n] });
+    let (z,inertia) = ldlt_solve(&K,&rhs,0f64)?;
+    let x = DVec::from_fn(n,|i,_| z[i]);
+    let y = DVec::from_fn(m,|i,_| z[n+i]);
+    Ok((x,y,inertia))
+}
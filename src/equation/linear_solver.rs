@@ -0,0 +1,173 @@
+use std::fmt;
+use crate::{
+    Result, DVec, DMat,
+    error::*
+};
+use super::{cholesky_solve_regularized, qr_solve, ldlt_solve, svd_solve};
+
+
+
+/// Solution of a linear equation together with the solver which computed it.
+///
+#[derive(Clone, Debug)]
+pub struct LinearSolution {
+    pub x: DVec,
+    /// id of the solver which computed x
+    pub solver: String,
+    /// numerical rank of the matrix if the solver determines it (SVD)
+    pub rank: Option<usize>,
+}
+
+impl fmt::Display for LinearSolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rank = match self.rank { Some(r) => r.to_string(), None => String::from("-") };
+        f.write_str(format!("solver: {}, rank: {}, x: {:.4}",self.solver,rank,self.x).as_str())
+    }
+}
+
+
+/// Solver for the regularized equation $(H+l*I)x=b$, H symmetric. As in
+/// cholesky_solve_regularized the regularization is applied to the Ruiz equilibrated matrix.
+///
+pub trait LinearSolver {
+
+    fn id(&self) -> String;
+    fn solve(&self, H: &DMat, b: &DVec, l: f64) -> Result<LinearSolution>;
+}
+
+
+/// Checks that the solution is finite and wraps it into a LinearSolution.
+fn finite_solution(x: DVec, solver: String, err: ErrKind) -> Result<LinearSolution> {
+
+    if x.iter().all(|x_i| x_i.is_finite()) { Ok(LinearSolution{ x, solver, rank: None }) }
+    else { Err(ConvOptError::new(err)) }
+}
+
+
+/// cholesky_solve_regularized, needs $H+l*I$ positive definite.
+pub struct CholeskySolver {}
+
+impl LinearSolver for CholeskySolver {
+
+    fn id(&self) -> String { String::from("Cholesky") }
+    fn solve(&self, H: &DMat, b: &DVec, l: f64) -> Result<LinearSolution> {
+        finite_solution(cholesky_solve_regularized(H,b,l)?,self.id(),
+            ErrKind::CholeskyFailure("non finite solution"))
+    }
+}
+
+
+/// qr_solve, needs $H+l*I$ nonsingular.
+pub struct QRSolver {}
+
+impl LinearSolver for QRSolver {
+
+    fn id(&self) -> String { String::from("QR") }
+    fn solve(&self, H: &DMat, b: &DVec, l: f64) -> Result<LinearSolution> {
+        finite_solution(qr_solve(H,b,l)?,self.id(),
+            ErrKind::QRSolveFailure("non finite solution"))
+    }
+}
+
+
+/// ldlt_solve, needs $H+l*I$ nonsingular, handles indefinite matrices.
+/// With `positive_definite` the solve fails unless the inertia shows that $H+l*I$ is positive
+/// definite, so the solution of a Newton equation $(H+l*I)p=-g$ is a descent direction.
+pub struct LDLTSolver {
+    pub positive_definite: bool,
+}
+
+impl LDLTSolver {
+    pub fn new(positive_definite: bool) -> LDLTSolver { LDLTSolver{ positive_definite } }
+}
+
+impl LinearSolver for LDLTSolver {
+
+    fn id(&self) -> String { String::from("LDLT") }
+    fn solve(&self, H: &DMat, b: &DVec, l: f64) -> Result<LinearSolution> {
+
+        let (x,inertia) = ldlt_solve(H,b,l)?;
+        if self.positive_definite && inertia.positive < b.len() {
+            return Err(ConvOptError::new(ErrKind::LDLTFailure("matrix not positive definite")));
+        }
+        finite_solution(x,self.id(),ErrKind::LDLTFailure("non finite solution"))
+    }
+}
+
+
+/// Minimum norm solution of $(H+l*I)x=b$ by svd_solve, singular values below
+/// rcond*(largest singular value) are treated as zero. Works for singular matrices and
+/// reports the numerical rank. Unlike the other solvers H is not equilibrated (this would
+/// change the minimized norm), so the regularization l is applied to H itself.
+pub struct SVDSolver {
+    pub rcond: f64,
+}
+
+impl SVDSolver {
+    pub fn new(rcond: f64) -> SVDSolver { SVDSolver{ rcond } }
+}
+
+impl LinearSolver for SVDSolver {
+
+    fn id(&self) -> String { String::from("SVD") }
+    fn solve(&self, H: &DMat, b: &DVec, l: f64) -> Result<LinearSolution> {
+
+        let n = H.nrows();
+        assert!(n==H.ncols() && n==b.len() && l>= 0f64);
+        let (x,rank) = if l <= 0f64 { svd_solve(H,b,self.rcond)? }
+            else { svd_solve(&(H+l*DMat::identity(n,n)),b,self.rcond)? };
+        let mut sol = finite_solution(x,self.id(),ErrKind::SVDFailure("non finite solution"))?;
+        sol.rank = Some(rank);
+        Ok(sol)
+    }
+}
+
+
+/// Tries the solvers in order and returns the first solution found, the id of the solver
+/// which succeeded is recorded in the solution. Fails with the error of the last solver
+/// if all of them fail.
+///
+pub struct FallbackSolver {
+    pub solvers: Vec<Box<dyn LinearSolver>>,
+}
+
+impl FallbackSolver {
+    pub fn new(solvers: Vec<Box<dyn LinearSolver>>) -> FallbackSolver {
+        assert!(!solvers.is_empty(),"FallbackSolver needs at least one solver");
+        FallbackSolver{ solvers }
+    }
+}
+
+impl Default for FallbackSolver {
+    /// Cholesky, LDLT requiring a positive definite inertia (catches matrices on which
+    /// Cholesky fails by rounding), SVD with rcond = 1e-12 (singular matrices). QR is not
+    /// included: it fails only on exactly singular matrices, so it would accept indefinite
+    /// ones and stop the chain with a non-descent direction.
+    fn default() -> FallbackSolver {
+        FallbackSolver::new(vec![
+            Box::new(CholeskySolver{}),
+            Box::new(LDLTSolver::new(true)),
+            Box::new(SVDSolver::new(1e-12)),
+        ])
+    }
+}
+
+impl LinearSolver for FallbackSolver {
+
+    fn id(&self) -> String {
+        let ids: Vec<String> = self.solvers.iter().map(|s| s.id()).collect();
+        format!("Fallback({})",ids.join(", "))
+    }
+
+    fn solve(&self, H: &DMat, b: &DVec, l: f64) -> Result<LinearSolution> {
+
+        let mut last_err = None;
+        for solver in self.solvers.iter() {
+            match solver.solve(H,b,l) {
+                Ok(sol) => return Ok(sol),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap())
+    }
+}
@@ -1,38 +1,85 @@
+//! Dense and sparse linear equation solvers.
+//!
+//! Generic over the scalar type (see crate::Real): the Cholesky and QR solves (also with
+//! a selectable Equilibration), cholesky_solve_conditioned, auto_solve, svd_solve, the
+//! triangular solves and the Cholesky update. The remaining solvers (LDLT, modified Cholesky,
+//! RegularizedSolver, the refined and mixed precision solves, pcg_solve, StructuredMatrix
+//! and the LinearSolver chain) serve the f64 optimization code and are f64 only.
+
+pub use self::{
+    ldlt::*,
+    modified_cholesky::*,
+    regularized_solver::*,
+    refinement::*,
+    pcg::*,
+    cholesky_update::*,
+    structured::*,
+    triangular::*,
+    linear_solver::*
+};
 use crate::{
-    Result, DVec, DMat,
+    Result, DVector, DMatrix, Real, real,
     error::*,
     matrix_utils::*
 };
 
 
+mod ldlt;
+mod modified_cholesky;
+mod regularized_solver;
+mod refinement;
+mod pcg;
+mod cholesky_update;
+mod structured;
+mod triangular;
+mod linear_solver;
+
+
 
 
 /// Solve the regularized equation $(H+l*I)x=b$ by Cholesky factorization of
 /// $H+l*I$. The matrix $H+l*I$ needs to be positive definite, i.e. if $H$ is singular
-/// we must have $l>0$. Uses Ruiz preconditioning of $H+l*I$.
+/// we must have $l>0$. Uses Ruiz preconditioning of $H+l*I$, this is
+/// cholesky_solve_equilibrated with the strategy Ruiz(5,5).
+///
+/// # Arguments
+///
+/// * `H`: positive semidefinite symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn cholesky_solve_regularized<T: Real>(H: &DMatrix<T>, b: &DVector<T>, l: T) -> Result<DVector<T>> {
+
+    cholesky_solve_equilibrated(H,b,l,&Ruiz::default())
+}
+
+
+/// Solve the regularized equation $(H+l*I)x=b$ by Cholesky factorization as
+/// cholesky_solve_regularized but with the equilibration strategy `eq` instead of Ruiz(5,5).
+/// The regularization is applied to the equilibrated matrix.
 ///
 /// # Arguments
 ///
 /// * `H`: positive semidefinite symmetric square matrix
 /// * `l`: nonnegative scalar (regularization parameter)
 /// * `b`: vector of same dimension as H
+/// * `eq`: equilibration strategy
 ///
-pub fn cholesky_solve_regularized(H: &DMat, b: &DVec, l: f64) -> Result<DVec> {
+pub fn cholesky_solve_equilibrated<T: Real>(
+    H: &DMatrix<T>, b: &DVector<T>, l: T, eq: &dyn Equilibration<T>
+) -> Result<DVector<T>> {
 
     let n = H.shape().0;  // number of rows
-    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+    assert!(n==H.shape().1 && n==b.len() && l>= T::zero());
 
-    // ruiz equilibration to improve the condition number
-    let n_e: usize = 5;   // rounds of ||.||_oo and ||.||_2 equilibration
-    let (d,B) = ruiz_equilibration(H,n_e,n_e);
-    // regularization
-    let G: DMat = if l <= 0f64 { B } else { B + l*DMat::identity(n,n) };
+    // equilibration to improve the condition number
+    let (d,G) = equilibrated_regularized_with(H,l,eq);
 
     if let Some(ch) = G.cholesky() {
 
-        let c = DVec::from_fn(n,|i,_| b[i]*d[i]);
+        let c = DVector::from_fn(n,|i,_| b[i]*d[i]);
         let u = ch.solve(&c);
-        Ok(DVec::from_fn(n,|i,_| u[i]*d[i]))
+        Ok(DVector::from_fn(n,|i,_| u[i]*d[i]))
     } else {
         Err(ConvOptError::new(ErrKind::CholeskyFailure("in cholesky_solve")))
     }
@@ -41,57 +88,169 @@ pub fn cholesky_solve_regularized(H: &DMat, b: &DVec, l: f64) -> Result<DVec> {
 
 
 /// Solve the regularized equation $(H+l*I)x=b$ by QR factorization of
-/// $H+l*I$. Uses Ruiz preconditioning of $H+l*I$.
+/// $H+l*I$. Uses Ruiz preconditioning of $H+l*I$, this is qr_solve_equilibrated with
+/// the strategy Ruiz(5,5).
+///
+/// # Arguments
+///
+/// * `H`: square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn qr_solve<T: Real>(H: &DMatrix<T>, b: &DVector<T>, l: T) -> Result<DVector<T>> {
+
+    qr_solve_equilibrated(H,b,l,&Ruiz::default())
+}
+
+
+/// Solve the regularized equation $(H+l*I)x=b$ by QR factorization as qr_solve but with
+/// the equilibration strategy `eq` instead of Ruiz(5,5).
 ///
 /// # Arguments
 ///
 /// * `H`: square matrix
 /// * `l`: nonnegative scalar (regularization parameter)
 /// * `b`: vector of same dimension as H
+/// * `eq`: equilibration strategy
 ///
-pub fn qr_solve(H: &DMat, b: &DVec, l: f64) -> Result<DVec> {
+pub fn qr_solve_equilibrated<T: Real>(
+    H: &DMatrix<T>, b: &DVector<T>, l: T, eq: &dyn Equilibration<T>
+) -> Result<DVector<T>> {
 
     let n = H.shape().0;  // number of rows
-    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+    assert!(n==H.shape().1 && n==b.len() && l>= T::zero());
 
     // first equilibrate, then apply regularization!
-    let n_e: usize = 5;   // rounds of ||.||_oo and ||.||_2 equilibration
-    let (d,B) = ruiz_equilibration(H,n_e,n_e);
-    // regularization
-    let G: DMat = if l <= 0f64 { B } else { B + l*DMat::identity(n,n) };
+    let (d,G) = equilibrated_regularized_with(H,l,eq);
 
     let qr = G.qr();
-    let c = DVec::from_fn(n,|i,_| b[i]*d[i]);
+    let c = DVector::from_fn(n,|i,_| b[i]*d[i]);
     if let Some(u) = qr.solve(&c) {
-        Ok(DVec::from_fn(n,|i,_| u[i]*d[i]))
+        Ok(DVector::from_fn(n,|i,_| u[i]*d[i]))
     } else {
         Err(ConvOptError::new(ErrKind::QRSolveFailure("in qr_solve")))
     }
 }
 
-/// Assuming that L is a lower triangular matrix, solves (L+lI)x=y, where I s the identity matrix.
-/// Only the lower triangular part of L is used, lower triangularity is not checked.
+/// Ruiz(5,5) equilibration $B=DHD$ of the square matrix H followed by the regularization
+/// $B+l*I$, the first step of the dense solvers. Returns (d,B+l*I).
+pub(crate) fn equilibrated_regularized<T: Real>(H: &DMatrix<T>, l: T) -> (DVector<T>, DMatrix<T>) {
+
+    equilibrated_regularized_with(H,l,&Ruiz::default())
+}
+
+
+/// As equilibrated_regularized with the equilibration strategy `eq`.
+pub(crate) fn equilibrated_regularized_with<T: Real>(
+    H: &DMatrix<T>, l: T, eq: &dyn Equilibration<T>
+) -> (DVector<T>, DMatrix<T>) {
+
+    let n = H.nrows();
+    let (d,B) = eq.equilibrate(H);
+    let G = if l <= T::zero() { B } else { B + DMatrix::identity(n,n)*l };
+    (d,G)
+}
+
+/// Condition number above which auto_solve uses QR instead of Cholesky factorization.
+pub const COND_MAX_CHOLESKY: f64 = 1e10;
+
+
+/// Solve the regularized equation $(H+l*I)x=b$ as cholesky_solve_regularized and estimate
+/// the 1-norm condition number of the equilibrated, regularized matrix from the Cholesky
+/// factor (Hager/Higham estimator, O(n²) extra cost).
 ///
+/// Returns (x, condition estimate).
 ///
-pub fn forward_solve(L: &DMat, y: &DVec, l:f64) -> Result<DVec> {
+/// # Arguments
+///
+/// * `H`: positive semidefinite symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn cholesky_solve_conditioned<T: Real>(
+    H: &DMatrix<T>, b: &DVector<T>, l: T
+) -> Result<(DVector<T>,T)> {
+
+    let n = H.shape().0;  // number of rows
+    assert!(n==H.shape().1 && n==b.len() && l>= T::zero());
 
-    let n=L.shape().0;
-    assert!(L.shape().1==n && y.len()==n);
-    let normL = L.norm();
-    let mut x = DVec::repeat(n,0f64);
-    let mut i=0;
-    while i<n {
+    let (d,G) = equilibrated_regularized(H,l);
 
-        if (l+L[(i,i)]).abs()<1e-20*normL {
-            return Err(ConvOptError::new(ErrKind::ForwardSolveFailure("Zero diagonal element")));
-        }
-        let mut s = 0f64;  // \sum_{j<i}L_ijx_j
-        let mut j=0;
-        while j< i { s+=L[(i,j)]*x[j]; j+=1; }
+    if let Some(ch) = G.clone().cholesky() {
 
-        x[i] = (y[i]-s)/(l+L[(i,i)]);
-        i+=1;
+        let cond = cholesky_condition_estimate(&G,&ch);
+        let c = DVector::from_fn(n,|i,_| b[i]*d[i]);
+        let u = ch.solve(&c);
+        Ok((DVector::from_fn(n,|i,_| u[i]*d[i]),cond))
+    } else {
+        Err(ConvOptError::new(ErrKind::CholeskyFailure("in cholesky_solve_conditioned")))
     }
+}
+
+
+/// Solve the regularized equation $(H+l*I)x=b$ choosing the method automatically:
+/// Cholesky factorization (cholesky_solve_conditioned) if the estimated condition number
+/// is at most COND_MAX_CHOLESKY, QR factorization (qr_solve) if it is larger.
+/// If the Cholesky factorization fails ($H+l*I$ not positive definite) the error is returned,
+/// the caller decides how to treat an indefinite matrix.
+///
+/// Returns (x, "Cholesky" or "QR", condition estimate).
+///
+/// # Arguments
+///
+/// * `H`: positive semidefinite symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn auto_solve<T: Real>(
+    H: &DMatrix<T>, b: &DVector<T>, l: T
+) -> Result<(DVector<T>,&'static str,T)> {
+
+    let (x,cond) = cholesky_solve_conditioned(H,b,l)?;
+    if cond <= real(COND_MAX_CHOLESKY) {
+        Ok((x,"Cholesky",cond))
+    } else {
+        Ok((qr_solve(H,b,l)?,"QR",cond))
+    }
+}
+
+/// Minimum norm least squares solution of $Hx=b$ by singular value decomposition $H=U\Sigma V'$:
+/// $x=V\Sigma^+U'b$, where singular values $\sigma_i\leq rcond*\sigma_{max}$ are treated as zero.
+/// Works for singular (e.g. Hessians of linear or partially linear functions) and rectangular
+/// matrices H, no equilibration is applied since it would change the norm which is minimized.
+///
+/// Returns (x, numerical rank of H).
+///
+/// # Arguments
+///
+/// * `H`: m x n matrix
+/// * `b`: vector of dimension m
+/// * `rcond`: relative threshold for the singular values, e.g. 1e-12
+///
+pub fn svd_solve<T: Real>(H: &DMatrix<T>, b: &DVector<T>, rcond: T) -> Result<(DVector<T>,usize)> {
+
+    assert!(H.nrows()==b.len() && rcond >= T::zero());
+    let svd = H.clone().svd(true,true);
+    let sigma_max = svd.singular_values.max();
+    let eps = rcond*sigma_max;
+    let rank = svd.singular_values.iter().filter(|&&s| s > eps).count();
+    if rank == 0 {
+        return Ok((DVector::zeros(H.ncols()),0));
+    }
+    let x = svd.solve(b,eps).map_err(|_|
+        ConvOptError::new(ErrKind::SVDFailure("in svd_solve"))
+    )?;
+    Ok((x,rank))
+}
+
+/// Assuming that L is a lower triangular matrix, solves (L+lI)x=y, where I s the identity matrix.
+/// Only the lower triangular part of L is used, lower triangularity is not checked.
+///
+///
+pub fn forward_solve<T: Real>(L: &DMatrix<T>, y: &DVector<T>, l:T) -> Result<DVector<T>> {
+
+    let mut x = y.clone();
+    forward_solve_in_place(L,&mut x,l)?;
     Ok(x)
 }
 
@@ -99,27 +258,10 @@ pub fn forward_solve(L: &DMat, y: &DVec, l:f64) -> Result<DVec> {
 /// Assuming that U is an upper triangular matrix, solves (U+lI)x=y, where I s the identity matrix.
 /// Only the upper triangular part of U is used, upper triangularity is not checked.
 ///
-pub fn back_solve(U: &DMat, y: &DVec, l:f64) -> Result<DVec> {
-
-    let n = U.shape().0;
-    assert!(U.shape().1==n && y.len()==n);
-    let normL = U.norm();
-    let mut x = DVec::repeat(n,0f64);
-    let mut i= n-1;
-    let mut go_on = true;    // checks i>=0
-    while go_on {
-
-        if (l+U[(i,i)]).abs()<1e-20*normL {
-            return Err(ConvOptError::new(ErrKind::BackSolveFailure("Zero diagonal element")));
-        }
-        let mut s = 0f64;  // \sum_{j>}U_ijx_j
-        let mut j = n-1;
-        while j>i { s+=U[(i,j)]*x[j]; j-=1; }
-
-        x[i] = (y[i]-s)/(l+U[(i,i)]);
-        // note i-=1 will fail for i==0 because of i:usize
-        if i==0 { go_on=false; } else { i-=1} ;
-    }
+pub fn back_solve<T: Real>(U: &DMatrix<T>, y: &DVector<T>, l:T) -> Result<DVector<T>> {
+
+    let mut x = y.clone();
+    back_solve_in_place(U,&mut x,l)?;
     Ok(x)
 }
 
@@ -0,0 +1,121 @@
+use crate::{
+    Result, DVec, DMat,
+    error::*
+};
+use super::equilibrated_regularized;
+
+
+
+/// Modified Cholesky factorization $A+E=LDL'$ (Gill-Murray-Wright) of a symmetric matrix A,
+/// where L is unit lower triangular, D is a positive diagonal matrix and E is a nonnegative
+/// diagonal matrix which is zero if A is sufficiently positive definite.
+///
+/// The pivots $d_j$ are bounded below by $\max_{i>j}|c_{ij}|^2/\beta^2$ and $\delta$, which
+/// bounds the elements of $L\sqrt{D}$ by $\beta$ so the factorization is stable, and E
+/// is only as large as needed for this.
+///
+#[derive(Clone, Debug)]
+pub struct ModifiedCholesky {
+    /// unit lower triangular factor
+    pub L: DMat,
+    /// diagonal of D
+    pub D: DVec,
+    /// diagonal of the shift E
+    pub E: DVec,
+}
+
+impl ModifiedCholesky {
+
+    /// Modified Cholesky factorization of the symmetric matrix A (only the lower triangle is used).
+    pub fn new(A: &DMat) -> ModifiedCholesky {
+
+        let n = A.nrows();
+        assert!(A.ncols()==n,"matrix A not square, rows={}, cols={}",n,A.ncols());
+
+        let mut gamma = 0f64;   // max |a_ii|
+        let mut xi = 0f64;      // max |a_ij|, i>j
+        for j in 0..n {
+            gamma = gamma.max(A[(j,j)].abs());
+            for i in j+1..n { xi = xi.max(A[(i,j)].abs()); }
+        }
+        let nu = if n > 1 { ((n*n-1) as f64).sqrt() } else { 1f64 };
+        let beta2 = gamma.max(xi/nu).max(f64::EPSILON);
+        let delta = f64::EPSILON*(gamma+xi).max(1f64);
+
+        let mut L = DMat::identity(n,n);
+        let mut D = DVec::repeat(n,0f64);
+        let mut E = DVec::repeat(n,0f64);
+        // column j of C: c_ij = a_ij - sum_{s<j} d_s l_is l_js, i>=j
+        let mut c = DVec::repeat(n,0f64);
+        for j in 0..n {
+
+            for i in j..n {
+                let mut c_ij = A[(i,j)];
+                for s in 0..j { c_ij -= D[s]*L[(i,s)]*L[(j,s)]; }
+                c[i] = c_ij;
+            }
+            let theta = (j+1..n).map(|i| c[i].abs()).fold(0f64,f64::max);
+            let d_j = c[j].abs().max(theta*theta/beta2).max(delta);
+            D[j] = d_j;
+            E[j] = d_j-c[j];
+            for i in j+1..n { L[(i,j)] = c[i]/d_j; }
+        }
+        ModifiedCholesky{ L, D, E }
+    }
+
+    /// The largest diagonal element of E, zero if A was factored without modification.
+    pub fn shift(&self) -> f64 { self.E.amax() }
+
+    /// Solves (A+E)x=b.
+    pub fn solve(&self, b: &DVec) -> DVec {
+
+        let n = self.D.len();
+        assert!(b.len()==n);
+        let mut x = b.clone();
+        for j in 0..n {
+            let x_j = x[j];
+            for i in j+1..n { x[i] -= self.L[(i,j)]*x_j; }
+        }
+        for j in 0..n { x[j] /= self.D[j]; }
+        for j in (0..n).rev() {
+            let mut s = x[j];
+            for i in j+1..n { s -= self.L[(i,j)]*x[i]; }
+            x[j] = s;
+        }
+        x
+    }
+}
+
+
+
+/// Solve the regularized equation $(H+l*I+E)x=b$, where E is the diagonal shift of the
+/// modified Cholesky factorization of $H+l*I$. Uses Ruiz preconditioning of $H$ with
+/// regularization and shift applied to the equilibrated matrix as in cholesky_solve_regularized.
+/// Unlike cholesky_solve_regularized this does not fail if $H+l*I$ is (nearly) singular or
+/// indefinite.
+///
+/// Returns the solution x and the largest diagonal element of E (zero if $H+l*I$ is safely
+/// positive definite).
+///
+/// # Arguments
+///
+/// * `H`: symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn modified_cholesky_solve(H: &DMat, b: &DVec, l: f64) -> Result<(DVec,f64)> {
+
+    let n = H.shape().0;  // number of rows
+    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+
+    let (d,G) = equilibrated_regularized(H,l);
+
+    let mc = ModifiedCholesky::new(&G);
+    let c = DVec::from_fn(n,|i,_| b[i]*d[i]);
+    let u = mc.solve(&c);
+    if u.iter().all(|u_i| u_i.is_finite()) {
+        Ok((DVec::from_fn(n,|i,_| u[i]*d[i]), mc.shift()))
+    } else {
+        Err(ConvOptError::new(ErrKind::CholeskyFailure("in modified_cholesky_solve")))
+    }
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+use crate::{
+    Result, DVec, DMat,
+    error::*,
+    matrix_utils::*,
+    sparse::{CscMatrix, sparse_ruiz_equilibration}
+};
+
+
+
+/// Linear operator $x\mapsto Ax$ given only through its action on vectors, so large systems
+/// can be solved without forming A (matrix-free Newton systems, Hessian-vector products).
+///
+pub trait LinearOperator {
+
+    fn dim(&self) -> usize;
+    /// the product Ax
+    fn apply(&self, x: &DVec) -> DVec;
+    /// Diagonal of A, the default applies A to all unit vectors (n products),
+    /// implementations which know the diagonal should override this.
+    fn diagonal(&self) -> DVec {
+        let n = self.dim();
+        let mut e = DVec::repeat(n,0f64);
+        DVec::from_fn(n,|i,_| {
+            e[i] = 1f64;
+            let a_ii = self.apply(&e)[i];
+            e[i] = 0f64;
+            a_ii
+        })
+    }
+}
+
+impl LinearOperator for DMat {
+
+    fn dim(&self) -> usize { self.nrows() }
+    fn apply(&self, x: &DVec) -> DVec { self*x }
+    fn diagonal(&self) -> DVec { DMat::diagonal(self) }
+}
+
+impl LinearOperator for CscMatrix {
+
+    fn dim(&self) -> usize { self.nrows }
+    fn apply(&self, x: &DVec) -> DVec { self.mul_vec(x) }
+    fn diagonal(&self) -> DVec { CscMatrix::diagonal(self) }
+}
+
+
+/// Linear operator defined by a closure computing Ax.
+///
+pub struct FnOperator<F: Fn(&DVec) -> DVec> {
+    pub dim: usize,
+    pub f: F,
+}
+
+impl<F: Fn(&DVec) -> DVec> FnOperator<F> {
+    pub fn new(dim: usize, f: F) -> FnOperator<F> { FnOperator{ dim, f } }
+}
+
+impl<F: Fn(&DVec) -> DVec> LinearOperator for FnOperator<F> {
+
+    fn dim(&self) -> usize { self.dim }
+    fn apply(&self, x: &DVec) -> DVec { (self.f)(x) }
+}
+
+
+
+//------------------ Preconditioners ------------------//
+
+
+/// Preconditioner M for the conjugate gradient method, applies $M^{-1}$, where M is a
+/// symmetric positive definite approximation of A.
+///
+pub trait Preconditioner {
+
+    fn id(&self) -> String;
+    /// the vector $M^{-1}r$
+    fn apply(&self, r: &DVec) -> DVec;
+}
+
+
+/// M = I (unpreconditioned conjugate gradient).
+pub struct IdentityPreconditioner {}
+
+impl Preconditioner for IdentityPreconditioner {
+
+    fn id(&self) -> String { String::from("identity") }
+    fn apply(&self, r: &DVec) -> DVec { r.clone() }
+}
+
+
+/// Diagonal preconditioner $M^{-1} = diag(s)$.
+///
+pub struct DiagonalPreconditioner {
+    pub id: String,
+    pub s: DVec,
+}
+
+impl DiagonalPreconditioner {
+
+    /// Jacobi preconditioner M = diag(A), fails if a diagonal element is not positive.
+    pub fn jacobi(op: &dyn LinearOperator) -> Result<DiagonalPreconditioner> {
+
+        let a = op.diagonal();
+        if a.iter().any(|&a_ii| a_ii <= 0f64 || a_ii.is_nan()) {
+            return Err(ConvOptError::new(
+                ErrKind::PreconditionerFailure("Jacobi preconditioner: nonpositive diagonal element")
+            ));
+        }
+        Ok(DiagonalPreconditioner{ id: String::from("Jacobi"), s: a.map(|a_ii| 1f64/a_ii) })
+    }
+
+    /// Preconditioner from Ruiz equilibration $B=DHD$: $M^{-1}=D^2$, so preconditioned
+    /// conjugate gradient works on the equilibrated matrix B.
+    pub fn ruiz(H: &DMat) -> DiagonalPreconditioner {
+
+        let (d,_) = ruiz_equilibration(H,5,5);
+        DiagonalPreconditioner::from_scaling(&d,"Ruiz")
+    }
+
+    /// Ruiz preconditioner for a sparse symmetric matrix, see `ruiz`.
+    pub fn sparse_ruiz(A: &CscMatrix) -> DiagonalPreconditioner {
+
+        let (d,_) = sparse_ruiz_equilibration(A,5,5);
+        DiagonalPreconditioner::from_scaling(&d,"Ruiz")
+    }
+
+    /// Preconditioner $M^{-1}=D^2$ from an already computed equilibration d, `id` names the
+    /// equilibration (e.g. "Ruiz").
+    pub fn from_scaling(d: &DVec, id: &str) -> DiagonalPreconditioner {
+        DiagonalPreconditioner{ id: String::from(id), s: d.map(|d_i| d_i*d_i) }
+    }
+}
+
+impl Preconditioner for DiagonalPreconditioner {
+
+    fn id(&self) -> String { self.id.clone() }
+    fn apply(&self, r: &DVec) -> DVec { r.component_mul(&self.s) }
+}
+
+
+/// Incomplete Cholesky factorization IC(0): $A\approx LL'$ where L has the sparsity pattern of
+/// the lower triangle of A. If the factorization breaks down (nonpositive pivot) it is
+/// retried on $A+\alpha diag(A)$ with increasing α (Manteuffel shift).
+///
+pub struct IncompleteCholesky {
+    /// diagonal shift α which was needed
+    pub shift: f64,
+    /// rows of L: (column, value) with increasing columns, the diagonal entry last
+    rows: Vec<Vec<(usize,f64)>>,
+}
+
+impl IncompleteCholesky {
+
+    /// IC(0) of the sparse symmetric matrix A (both triangles stored).
+    pub fn new(A: &CscMatrix) -> Result<IncompleteCholesky> {
+
+        let n = A.nrows;
+        assert!(A.ncols==n,"matrix A not square");
+        // lower triangle by rows = upper triangle of A by columns (A symmetric)
+        let pattern: Vec<Vec<(usize,f64)>> = (0..n).map(|i|
+            (A.col_ptr[i]..A.col_ptr[i+1]).filter(|&k| A.row_idx[k] <= i).
+                map(|k| (A.row_idx[k],A.values[k])).collect()
+        ).collect();
+
+        let mut alpha = 0f64;
+        for _ in 0..20 {
+            if let Some(rows) = IncompleteCholesky::factor(&pattern,alpha) {
+                return Ok(IncompleteCholesky{ shift: alpha, rows });
+            }
+            alpha = if alpha == 0f64 { 1e-3 } else { 2f64*alpha };
+        }
+        Err(ConvOptError::new(ErrKind::PreconditionerFailure("incomplete Cholesky factorization")))
+    }
+
+    fn factor(pattern: &[Vec<(usize,f64)>], alpha: f64) -> Option<Vec<Vec<(usize,f64)>>> {
+
+        let mut rows: Vec<Vec<(usize,f64)>> = Vec::with_capacity(pattern.len());
+        for (i,row_pattern) in pattern.iter().enumerate() {
+
+            let mut row: Vec<(usize,f64)> = Vec::with_capacity(row_pattern.len());
+            let mut a_ii = 0f64;
+            for &(j,a_ij) in row_pattern.iter() {
+
+                if j == i { a_ii = a_ij; continue; }
+                // l_ij = (a_ij - sum_{k<j} l_ik l_jk)/l_jj
+                let row_j = &rows[j];
+                let s = sparse_dot(&row,row_j);
+                let l_jj = row_j.last().unwrap().1;
+                row.push((j,(a_ij-s)/l_jj));
+            }
+            let d = a_ii*(1f64+alpha)-row.iter().map(|&(_,l)| l*l).sum::<f64>();
+            if d <= 0f64 || d.is_nan() { return None; }
+            row.push((i,d.sqrt()));
+            rows.push(row);
+        }
+        Some(rows)
+    }
+}
+
+/// Sum of u_k v_k over the common columns k, both lists sorted by column.
+fn sparse_dot(u: &[(usize,f64)], v: &[(usize,f64)]) -> f64 {
+
+    let (mut p,mut q) = (0,0);
+    let mut s = 0f64;
+    while p < u.len() && q < v.len() {
+        if u[p].0 == v[q].0 { s += u[p].1*v[q].1; p += 1; q += 1; }
+        else if u[p].0 < v[q].0 { p += 1; } else { q += 1; }
+    }
+    s
+}
+
+impl Preconditioner for IncompleteCholesky {
+
+    fn id(&self) -> String { String::from("IC(0)") }
+
+    fn apply(&self, r: &DVec) -> DVec {
+
+        let n = self.rows.len();
+        // Ly = r
+        let mut y = r.clone();
+        for i in 0..n {
+            let row = &self.rows[i];
+            let (diag,off) = row.split_last().unwrap();
+            let s: f64 = off.iter().map(|&(k,l)| l*y[k]).sum();
+            y[i] = (y[i]-s)/diag.1;
+        }
+        // L'x = y
+        for i in (0..n).rev() {
+            let row = &self.rows[i];
+            let (diag,off) = row.split_last().unwrap();
+            y[i] /= diag.1;
+            let x_i = y[i];
+            for &(k,l) in off.iter() { y[k] -= l*x_i; }
+        }
+        y
+    }
+}
+
+
+
+//------------------ Conjugate gradient ------------------//
+
+
+#[derive(Clone, Debug)]
+pub struct PcgResult {
+    pub x: DVec,
+    pub iterations: usize,
+    /// ||b-Ax_k|| for k = 0,1,...,iterations
+    pub residual_norms: Vec<f64>,
+    /// true if ||b-Ax|| <= tol*||b||
+    pub converged: bool,
+}
+
+impl fmt::Display for PcgResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "converged: {}, iterations: {}, ||residual||: {:.3e}",
+            self.converged, self.iterations, self.residual_norms.last().unwrap()
+        ).as_str())
+    }
+}
+
+
+/// Solves Ax=b for a symmetric positive definite linear operator A with the preconditioned
+/// conjugate gradient method starting from x=0. Stops when $||b-Ax||\leq tol*||b||$ or after
+/// max_iter iterations (then `converged` is false). The residual norm of each iterate is
+/// recorded in the result.
+///
+/// Fails if a search direction p with $p'Ap\leq 0$ is encountered, i.e. A is not positive
+/// definite (ConvergenceFailure), or if $r'z\leq 0$ for a residual r and the preconditioned
+/// residual z, i.e. the preconditioner is not positive definite (PreconditionerFailure).
+///
+/// # Arguments
+///
+/// * `op`: symmetric positive definite linear operator
+/// * `b`: right hand side
+/// * `precond`: preconditioner, use IdentityPreconditioner for plain conjugate gradient
+/// * `tol`: relative residual tolerance
+/// * `max_iter`: maximal number of iterations
+///
+pub fn pcg_solve(
+    op: &dyn LinearOperator, b: &DVec, precond: &dyn Preconditioner, tol: f64, max_iter: usize
+) -> Result<PcgResult> {
+
+    let n = op.dim();
+    assert!(b.len()==n);
+
+    let norm_b = b.norm();
+    let mut x = DVec::repeat(n,0f64);
+    let mut r = b.clone();
+    let mut residual_norms = vec![norm_b];
+    if norm_b == 0f64 {
+        return Ok(PcgResult{ x, iterations: 0, residual_norms, converged: true });
+    }
+    let check_rz = |rz: f64| -> Result<f64> {
+        if rz > 0f64 && rz.is_finite() { return Ok(rz); }
+        Err(ConvOptError::new(
+            ErrKind::PreconditionerFailure("pcg_solve: preconditioner not positive definite")
+        ))
+    };
+    let mut z = precond.apply(&r);
+    let mut p = z.clone();
+    let mut rz = check_rz(r.dot(&z))?;
+    let mut iter = 0;
+    while iter < max_iter {
+
+        let Ap = op.apply(&p);
+        let pAp = p.dot(&Ap);
+        if pAp <= 0f64 || pAp.is_nan() {
+            return Err(ConvOptError::new(
+                ErrKind::ConvergenceFailure("pcg_solve: operator not positive definite")
+            ));
+        }
+        let alpha = rz/pAp;
+        x += alpha*&p;
+        r -= alpha*&Ap;
+        iter += 1;
+        let norm_r = r.norm();
+        residual_norms.push(norm_r);
+        if norm_r <= tol*norm_b {
+            return Ok(PcgResult{ x, iterations: iter, residual_norms, converged: true });
+        }
+        z = precond.apply(&r);
+        let rz_new = check_rz(r.dot(&z))?;
+        p = &z+(rz_new/rz)*&p;
+        rz = rz_new;
+    }
+    Ok(PcgResult{ x, iterations: iter, residual_norms, converged: false })
+}
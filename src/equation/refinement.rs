@@ -0,0 +1,262 @@
+use std::fmt;
+use crate::{
+    Result, DVec, DMat,
+    error::*,
+    matrix_utils::*
+};
+use super::equilibrated_regularized;
+
+
+
+/// Solution of a linear equation $Mx=b$ with accuracy information.
+///
+#[derive(Clone, Debug)]
+pub struct SolveReport {
+    pub x: DVec,
+    /// $||b-Mx||$
+    pub residual_norm: f64,
+    /// normwise backward error $||b-Mx||/(||M||*||x||+||b||)$, x is the exact solution
+    /// of a system perturbed relatively by this much. Values near f64::EPSILON mean the
+    /// solution is as good as the data, values near 1 mean it is garbage.
+    pub backward_error: f64,
+    /// number of iterative refinement steps which were applied
+    pub refinement_steps: usize,
+}
+
+impl fmt::Display for SolveReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "||residual||: {:.3e}, backward error: {:.3e}, refinement steps: {}",
+            self.residual_norm, self.backward_error, self.refinement_steps
+        ).as_str())
+    }
+}
+
+
+/// Error free transformation a+b = s+e with s = fl(a+b) (Knuth's TwoSum).
+fn two_sum(a: f64, b: f64) -> (f64,f64) {
+
+    let s = a+b;
+    let z = s-a;
+    let e = (a-(s-z))+(b-z);
+    (s,e)
+}
+
+
+/// Residual $b-(H+l*diag(1/d²))x$ accumulated in twice the working precision: products are
+/// split exactly with fused multiply-add, sums are compensated (Ogita-Rump-Oishi Dot2).
+///
+/// # Arguments
+///
+/// * `H`: square matrix
+/// * `reg`: diagonal added to H (empty if no regularization)
+///
+pub fn compensated_residual(H: &DMat, reg: &DVec, x: &DVec, b: &DVec) -> DVec {
+
+    let n = x.len();
+    let mut r = DVec::repeat(H.nrows(),0f64);
+    for i in 0..H.nrows() {
+
+        let (mut s,mut c) = (b[i],0f64);
+        let mut add = |p: f64| {
+            let (t,e) = two_sum(s,p);
+            s = t;
+            c += e;
+        };
+        for j in 0..n {
+            let p = -H[(i,j)]*x[j];
+            add(p);
+            add((-H[(i,j)]).mul_add(x[j],-p));   // exact rounding error of the product
+        }
+        if !reg.is_empty() {
+            let p = -reg[i]*x[i];
+            add(p);
+            add((-reg[i]).mul_add(x[i],-p));
+        }
+        r[i] = s+c;
+    }
+    r
+}
+
+
+/// Iterative refinement of the solution of $Mx=b$, $M=H+diag(reg)$, where `solve` applies
+/// an approximate inverse of M (e.g. from a factorization). Residuals are computed with
+/// compensated_residual. Stops after `max_refinements` steps, when the backward error reaches
+/// the working precision or when a step does not halve the correction (stagnation).
+///
+fn refine(
+    H: &DMat, reg: &DVec, b: &DVec, max_refinements: usize, solve: &dyn Fn(&DVec) -> DVec
+) -> SolveReport {
+
+    // Frobenius norm of M
+    let norm_M = if reg.is_empty() { H.norm() } else { (H+DMat::from_diagonal(reg)).norm() };
+    let norm_b = b.norm();
+    let backward_error = |x: &DVec, norm_r: f64| norm_r/(norm_M*x.norm()+norm_b).max(f64::MIN_POSITIVE);
+
+    let mut x = solve(b);
+    let mut r = compensated_residual(H,reg,&x,b);
+    let mut norm_r = r.norm();
+    let mut steps = 0;
+    let mut norm_dx_prev = f64::INFINITY;
+    while steps < max_refinements && backward_error(&x,norm_r) > f64::EPSILON {
+
+        let dx = solve(&r);
+        let norm_dx = dx.norm();
+        if !norm_dx.is_finite() || norm_dx > 0.5f64*norm_dx_prev { break; }
+        let x_new = &x+dx;
+        let r_new = compensated_residual(H,reg,&x_new,b);
+        let norm_r_new = r_new.norm();
+        if norm_r_new >= norm_r { break; }
+        x = x_new;
+        r = r_new;
+        norm_r = norm_r_new;
+        norm_dx_prev = norm_dx;
+        steps += 1;
+    }
+    let backward_error = backward_error(&x,norm_r);
+    SolveReport{ x, residual_norm: norm_r, backward_error, refinement_steps: steps }
+}
+
+
+/// The regularization $l*I$ applied to the equilibrated matrix $B=DHD$ corresponds to
+/// the diagonal $l/d_i²$ added to H.
+fn regularization_diagonal(d: &DVec, l: f64) -> DVec {
+
+    if l <= 0f64 { DVec::zeros(0) } else { DVec::from_fn(d.len(),|i,_| l/(d[i]*d[i])) }
+}
+
+
+
+/// Solves the regularized equation $(H+l*I)x=b$ as cholesky_solve_regularized and then applies
+/// at most `max_refinements` steps of iterative refinement with compensated residuals
+/// reusing the Cholesky factor. Since the regularization is applied after equilibration the
+/// matrix of the equation is $M=H+l*D^{-2}$, residual and backward error refer to M.
+///
+/// # Arguments
+///
+/// * `H`: positive semidefinite symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+/// * `max_refinements`: maximal number of refinement steps (0: only report accuracy)
+///
+pub fn cholesky_solve_refined(H: &DMat, b: &DVec, l: f64, max_refinements: usize) -> Result<SolveReport> {
+
+    let n = H.shape().0;  // number of rows
+    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+
+    let (d,G) = equilibrated_regularized(H,l);
+
+    if let Some(ch) = G.cholesky() {
+
+        let solve = |c: &DVec| -> DVec {
+            let u = ch.solve(&DVec::from_fn(n,|i,_| c[i]*d[i]));
+            DVec::from_fn(n,|i,_| u[i]*d[i])
+        };
+        Ok(refine(H,&regularization_diagonal(&d,l),b,max_refinements,&solve))
+    } else {
+        Err(ConvOptError::new(ErrKind::CholeskyFailure("in cholesky_solve_refined")))
+    }
+}
+
+
+
+/// Solves the regularized equation $(H+l*I)x=b$ as qr_solve and then applies at most
+/// `max_refinements` steps of iterative refinement with compensated residuals reusing the
+/// QR factorization. Residual and backward error refer to $M=H+l*D^{-2}$ as in
+/// cholesky_solve_refined.
+///
+/// # Arguments
+///
+/// * `H`: square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+/// * `max_refinements`: maximal number of refinement steps (0: only report accuracy)
+///
+pub fn qr_solve_refined(H: &DMat, b: &DVec, l: f64, max_refinements: usize) -> Result<SolveReport> {
+
+    let n = H.shape().0;  // number of rows
+    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+
+    let (d,G) = equilibrated_regularized(H,l);
+
+    let qr = G.qr();
+    if !qr.is_invertible() {
+        return Err(ConvOptError::new(ErrKind::QRSolveFailure("in qr_solve_refined")));
+    }
+    let solve = |c: &DVec| -> DVec {
+        let u = qr.solve(&DVec::from_fn(n,|i,_| c[i]*d[i])).unwrap_or_else(|| DVec::repeat(n,f64::NAN));
+        DVec::from_fn(n,|i,_| u[i]*d[i])
+    };
+    Ok(refine(H,&regularization_diagonal(&d,l),b,max_refinements,&solve))
+}
+
+
+
+/// Condition number of the equilibrated matrix above which mixed_precision_cholesky_solve
+/// does not attempt refinement of the f32 solution: each refinement step reduces the error
+/// roughly by the factor cond*f32::EPSILON, which must be well below 1/2.
+pub const COND_MAX_MIXED_PRECISION: f64 = 1e6;
+
+/// Backward error at which the refined mixed precision solution is accepted.
+const MIXED_PRECISION_TOL: f64 = 100f64*f64::EPSILON;
+
+/// Maximal number of refinement steps in mixed_precision_cholesky_solve.
+const MAX_MIXED_PRECISION_REFINEMENTS: usize = 20;
+
+
+/// Solves the regularized equation $(H+l*I)x=b$ with the Cholesky factorization computed in
+/// single precision (about half the cost of the f64 factorization) and iterative refinement
+/// with compensated f64 residuals, which recovers f64 accuracy if the Ruiz equilibrated matrix
+/// is moderately conditioned. Equilibration and regularization are as in
+/// cholesky_solve_regularized.
+///
+/// Falls back to the f64 Cholesky factorization of the same equilibrated matrix (the result
+/// of cholesky_solve_regularized) if the f32 factorization fails, the condition estimate
+/// (computed from the f32 factor) exceeds COND_MAX_MIXED_PRECISION or refinement stalls
+/// before reaching a backward error of 100*f64::EPSILON.
+///
+/// Returns (x, "mixed" or "double", condition estimate of the equilibrated matrix), the
+/// condition estimate is infinite if the f32 factorization failed.
+///
+/// # Arguments
+///
+/// * `H`: positive semidefinite symmetric square matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `b`: vector of same dimension as H
+///
+pub fn mixed_precision_cholesky_solve(H: &DMat, b: &DVec, l: f64) -> Result<(DVec,&'static str,f64)> {
+
+    let n = H.shape().0;  // number of rows
+    assert!(n==H.shape().1 && n==b.len() && l>= 0f64);
+
+    let (d,G) = equilibrated_regularized(H,l);
+
+    let mut cond = f64::INFINITY;
+    if let Some(ch) = G.map(|a| a as f32).cholesky() {
+
+        // solves Gu=c with the f32 factor
+        let solve_G = |c: &DVec| -> DVec { ch.solve(&c.map(|a| a as f32)).map(|a| a as f64) };
+        cond = norm_1(&G)*estimate_inverse_norm_1(n,&solve_G,&solve_G);
+        if cond <= COND_MAX_MIXED_PRECISION {
+
+            let solve = |c: &DVec| -> DVec {
+                let u = solve_G(&DVec::from_fn(n,|i,_| c[i]*d[i]));
+                DVec::from_fn(n,|i,_| u[i]*d[i])
+            };
+            let report = refine(
+                H,&regularization_diagonal(&d,l),b,MAX_MIXED_PRECISION_REFINEMENTS,&solve
+            );
+            if report.backward_error <= MIXED_PRECISION_TOL {
+                return Ok((report.x,"mixed",cond));
+            }
+        }
+    }
+    // full precision factorization of the already equilibrated, regularized matrix
+    if let Some(ch) = G.cholesky() {
+
+        let u = ch.solve(&DVec::from_fn(n,|i,_| b[i]*d[i]));
+        Ok((DVec::from_fn(n,|i,_| u[i]*d[i]),"double",cond))
+    } else {
+        Err(ConvOptError::new(ErrKind::CholeskyFailure("in mixed_precision_cholesky_solve")))
+    }
+}
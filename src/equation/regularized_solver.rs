@@ -0,0 +1,88 @@
+use nalgebra::SymmetricEigen;
+use crate::{
+    Result, DVec, DMat,
+    error::*
+};
+use super::equilibrated_regularized;
+
+
+
+/// Solver for the regularized equations $(H+l*I)x=b$ with a fixed symmetric matrix H and
+/// varying regularization parameters $l\geq 0$.
+///
+/// H is Ruiz equilibrated once, $B=DHD$, and the symmetric eigen decomposition $B=Q\Lambda Q'$
+/// is computed once (about 8 times the cost of a Cholesky factorization). After that each
+/// solve costs O(n²):
+///     $x = DQ(\Lambda+lI)^{-1}Q'Db$.
+/// As in cholesky_solve_regularized the regularization is applied to the equilibrated matrix,
+/// so `solve(b,l)` solves the same equation as `cholesky_solve_regularized(H,b,l)`.
+///
+/// This is deliberately not a LinearSolver: that interface receives H with every call, so the
+/// decomposition would be recomputed each time and cost more than a Cholesky factorization.
+/// It pays off where several regularization parameters are tried at the same H, as in
+/// solve_min_problem_with_options when Newton steps make no progress and the iterate stays
+/// put (see newton_step_with_regularized_solver).
+///
+#[derive(Clone, Debug)]
+pub struct RegularizedSolver {
+    /// equilibration: B = diag(d) H diag(d)
+    pub d: DVec,
+    /// eigenvalues of B
+    pub eigenvalues: DVec,
+    /// orthonormal eigenvectors of B (columns)
+    pub eigenvectors: DMat,
+}
+
+impl RegularizedSolver {
+
+    /// Equilibrates and decomposes the symmetric matrix H.
+    pub fn new(H: &DMat) -> RegularizedSolver {
+
+        let n = H.shape().0;
+        assert!(n==H.shape().1,"matrix H not square, rows={}, cols={}",n,H.shape().1);
+
+        let (d,B) = equilibrated_regularized(H,0f64);
+        let eigen = SymmetricEigen::new(B);
+        RegularizedSolver{ d, eigenvalues: eigen.eigenvalues, eigenvectors: eigen.eigenvectors }
+    }
+
+    pub fn dim(&self) -> usize { self.d.len() }
+
+    /// Smallest eigenvalue of the equilibrated matrix. `solve(b,l)` with $l\geq 0$ succeeds
+    /// exactly if $l$ is larger than the negative of this, i.e. for every l if the eigenvalue
+    /// is positive and for $l>|\lambda_{min}|$ if H is indefinite.
+    pub fn min_eigenvalue(&self) -> f64 { self.eigenvalues.min() }
+
+    /// 2-norm condition number of the equilibrated regularized matrix, infinite if it is not
+    /// positive definite.
+    pub fn condition_number(&self, l: f64) -> f64 {
+        let l_min = self.min_eigenvalue()+l;
+        if l_min <= 0f64 { f64::INFINITY } else { (self.eigenvalues.max()+l)/l_min }
+    }
+
+    /// Solves $(H+l*I)x=b$ (with l applied after equilibration) in O(n²).
+    /// Fails if $H+l*I$ is not positive definite.
+    ///
+    /// # Arguments
+    ///
+    /// * `b`: vector of same dimension as H
+    /// * `l`: nonnegative scalar (regularization parameter)
+    ///
+    pub fn solve(&self, b: &DVec, l: f64) -> Result<DVec> {
+
+        let n = self.dim();
+        assert!(n==b.len() && l >= 0f64);
+
+        let tol = 1e-14*self.eigenvalues.amax().max(l);
+        if self.min_eigenvalue()+l <= tol {
+            return Err(ConvOptError::new(
+                ErrKind::EigenSolveFailure("matrix H+lI not positive definite")
+            ));
+        }
+        let c = DVec::from_fn(n,|i,_| b[i]*self.d[i]);
+        let mut w: DVec = self.eigenvectors.tr_mul(&c);
+        for i in 0..n { w[i] /= self.eigenvalues[i]+l; }
+        let u: DVec = &self.eigenvectors*w;
+        Ok(DVec::from_fn(n,|i,_| u[i]*self.d[i]))
+    }
+}
@@ -0,0 +1,157 @@
+use crate::{
+    Result, DVector, DMatrix, Real, real,
+    error::*
+};
+
+
+
+/// Block size of the blocked triangular solves.
+const BLOCK_SIZE: usize = 64;
+
+
+/// Checks that no diagonal element of T+lI is (numerically) zero.
+fn check_diagonal<T: Real>(M: &DMatrix<T>, l: T, err: ErrKind) -> Result<()> {
+
+    let tol = real::<T>(1e-20)*M.norm();
+    if (0..M.nrows()).any(|i| (l+M[(i,i)]).abs() < tol) {
+        return Err(ConvOptError::new(err));
+    }
+    Ok(())
+}
+
+
+/// Forward substitution on the rows k0..k1 of the column y (column oriented, the entries
+/// of L are read column by column, which is contiguous in memory):
+/// overwrites y[k0..k1] with the solution of (L[k0..k1,k0..k1]+lI)x = y[k0..k1].
+fn forward_kernel<T: Real>(L: &[T], n: usize, y: &mut [T], k0: usize, k1: usize, l: T) {
+
+    for j in k0..k1 {
+        y[j] /= l+L[j*n+j];
+        let x_j = y[j];
+        for (y_i,l_ij) in y[j+1..k1].iter_mut().zip(L[j*n+j+1..j*n+k1].iter()) {
+            *y_i -= *l_ij*x_j;
+        }
+    }
+}
+
+
+/// Back substitution on the rows k0..k1 of the column y:
+/// overwrites y[k0..k1] with the solution of (U[k0..k1,k0..k1]+lI)x = y[k0..k1].
+fn back_kernel<T: Real>(U: &[T], n: usize, y: &mut [T], k0: usize, k1: usize, l: T) {
+
+    for j in (k0..k1).rev() {
+        y[j] /= l+U[j*n+j];
+        let x_j = y[j];
+        for (y_i,u_ij) in y[k0..j].iter_mut().zip(U[j*n+k0..j*n+j].iter()) {
+            *y_i -= *u_ij*x_j;
+        }
+    }
+}
+
+
+/// Assuming that L is a lower triangular matrix, solves (L+lI)x=y in place, i.e. y is
+/// overwritten with the solution x. Only the lower triangular part of L is used.
+///
+pub fn forward_solve_in_place<T: Real>(L: &DMatrix<T>, y: &mut DVector<T>, l: T) -> Result<()> {
+
+    let n = L.nrows();
+    assert!(L.ncols()==n && y.len()==n);
+    check_diagonal(L,l,ErrKind::ForwardSolveFailure("Zero diagonal element"))?;
+    forward_kernel(L.as_slice(),n,y.as_mut_slice(),0,n,l);
+    Ok(())
+}
+
+
+/// Assuming that U is an upper triangular matrix, solves (U+lI)x=y in place, i.e. y is
+/// overwritten with the solution x. Only the upper triangular part of U is used.
+///
+pub fn back_solve_in_place<T: Real>(U: &DMatrix<T>, y: &mut DVector<T>, l: T) -> Result<()> {
+
+    let n = U.nrows();
+    assert!(U.ncols()==n && y.len()==n);
+    check_diagonal(U,l,ErrKind::BackSolveFailure("Zero diagonal element"))?;
+    back_kernel(U.as_slice(),n,y.as_mut_slice(),0,n,l);
+    Ok(())
+}
+
+
+/// Assuming that L is a lower triangular n x n matrix, solves (L+lI)X=Y for an n x m matrix
+/// Y of right hand sides in place (Y is overwritten with X). Only the lower triangular part
+/// of L is used.
+///
+/// The solve is blocked: diagonal blocks of size BLOCK_SIZE are solved by substitution,
+/// the remaining rows are updated with a single matrix product per block, which keeps
+/// the working set in cache.
+///
+pub fn forward_solve_multiple_in_place<T: Real>(L: &DMatrix<T>, Y: &mut DMatrix<T>, l: T) -> Result<()> {
+
+    let n = L.nrows();
+    assert!(L.ncols()==n && Y.nrows()==n);
+    check_diagonal(L,l,ErrKind::ForwardSolveFailure("Zero diagonal element"))?;
+    let m = Y.ncols();
+    let mut k = 0;
+    while k < n {
+
+        let k1 = (k+BLOCK_SIZE).min(n);
+        for c in 0..m {
+            forward_kernel(L.as_slice(),n,Y.column_mut(c).as_mut_slice(),k,k1,l);
+        }
+        if k1 < n {
+            // Y[k1..n,:] -= L[k1..n,k..k1] X[k..k1,:]
+            let (X_k,mut Y_rest) = Y.rows_range_pair_mut(k..k1,k1..n);
+            Y_rest.gemm(-T::one(),&L.slice((k1,k),(n-k1,k1-k)),&X_k,T::one());
+        }
+        k = k1;
+    }
+    Ok(())
+}
+
+
+/// Assuming that U is an upper triangular n x n matrix, solves (U+lI)X=Y for an n x m matrix
+/// Y of right hand sides in place (Y is overwritten with X). Only the upper triangular part
+/// of U is used. Blocked as forward_solve_multiple_in_place.
+///
+pub fn back_solve_multiple_in_place<T: Real>(U: &DMatrix<T>, Y: &mut DMatrix<T>, l: T) -> Result<()> {
+
+    let n = U.nrows();
+    assert!(U.ncols()==n && Y.nrows()==n);
+    check_diagonal(U,l,ErrKind::BackSolveFailure("Zero diagonal element"))?;
+    let m = Y.ncols();
+    let mut k1 = n;
+    while k1 > 0 {
+
+        let k = k1.saturating_sub(BLOCK_SIZE);
+        for c in 0..m {
+            back_kernel(U.as_slice(),n,Y.column_mut(c).as_mut_slice(),k,k1,l);
+        }
+        if k > 0 {
+            // Y[0..k,:] -= U[0..k,k..k1] X[k..k1,:]
+            let (mut Y_rest,X_k) = Y.rows_range_pair_mut(0..k,k..k1);
+            Y_rest.gemm(-T::one(),&U.slice((0,k),(k,k1-k)),&X_k,T::one());
+        }
+        k1 = k;
+    }
+    Ok(())
+}
+
+
+/// Solves (L+lI)X=Y for lower triangular L and a matrix Y of right hand sides,
+/// see forward_solve_multiple_in_place.
+///
+pub fn forward_solve_multiple<T: Real>(L: &DMatrix<T>, Y: &DMatrix<T>, l: T) -> Result<DMatrix<T>> {
+
+    let mut X = Y.clone();
+    forward_solve_multiple_in_place(L,&mut X,l)?;
+    Ok(X)
+}
+
+
+/// Solves (U+lI)X=Y for upper triangular U and a matrix Y of right hand sides,
+/// see back_solve_multiple_in_place.
+///
+pub fn back_solve_multiple<T: Real>(U: &DMatrix<T>, Y: &DMatrix<T>, l: T) -> Result<DMatrix<T>> {
+
+    let mut X = Y.clone();
+    back_solve_multiple_in_place(U,&mut X,l)?;
+    Ok(X)
+}
@@ -1,6 +1,7 @@
 
 use std::{error::Error, fmt};
 use std::fmt::{Debug, Formatter, Display};
+use crate::DVec;
 
 
 /// Possible errors occurring in this library.
@@ -16,7 +17,44 @@ pub enum ErrKind {
     ForwardSolveFailure(&'static str),
     CholeskyFailure(&'static str),
     QRSolveFailure(&'static str),
+    LDLTFailure(&'static str),
+    EigenSolveFailure(&'static str),
+    SVDFailure(&'static str),
+    StructuredSolveFailure(&'static str),
+    /// a preconditioner could not be constructed
+    PreconditionerFailure(&'static str),
     ConvergenceFailure(&'static str),
+    /// the solver made no progress and all attempts to recover failed
+    Stalled(StallDiagnostics),
+}
+
+
+/// State of a solver at the point where it stalled.
+#[derive(Debug, Clone)]
+pub struct StallDiagnostics {
+    /// iteration at which the solver gave up
+    pub iteration: usize,
+    /// the last iterate
+    pub point: DVec,
+    pub objective_value: f64,
+    pub gradient_norm: f64,
+    pub trust_radius: f64,
+    /// regularization parameter of the last Newton step
+    pub lambda: f64,
+    /// the recovery measures which were tried, in order
+    pub recovery_attempts: Vec<&'static str>,
+}
+
+impl fmt::Display for StallDiagnostics {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "iteration: {0}, f(x): {1:.8}, ||grad(f)(x)||: {2:.4e}, trust radius: {3:.4e}, \
+            lambda: {4:.4e},\nrecovery attempts: {5}\nx: {6:.6}",
+            self.iteration, self.objective_value, self.gradient_norm, self.trust_radius,
+            self.lambda, self.recovery_attempts.join(", "), self.point
+        ).as_str())
+    }
 }
 
 
@@ -35,7 +73,7 @@ impl fmt::Display for ConvOptError {
 
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 
-        match self.kind {
+        match &self.kind {
 
             ErrKind::BackSolveFailure(msg) => {
                 let s = "Back solve failed: ".to_owned() + msg;
@@ -57,6 +95,30 @@ impl fmt::Display for ConvOptError {
                 let s = "qr_solve failed: ".to_owned() + msg;
                 f.write_str(s.as_str())
             }
+            ErrKind::LDLTFailure(msg) => {
+                let s = "LDLT factorization failed: ".to_owned() + msg;
+                f.write_str(s.as_str())
+            }
+            ErrKind::EigenSolveFailure(msg) => {
+                let s = "Solve by eigen decomposition failed: ".to_owned() + msg;
+                f.write_str(s.as_str())
+            }
+            ErrKind::SVDFailure(msg) => {
+                let s = "Solve by singular value decomposition failed: ".to_owned() + msg;
+                f.write_str(s.as_str())
+            }
+            ErrKind::StructuredSolveFailure(msg) => {
+                let s = "Structured solve failed: ".to_owned() + msg;
+                f.write_str(s.as_str())
+            }
+            ErrKind::PreconditionerFailure(msg) => {
+                let s = "Preconditioner construction failed: ".to_owned() + msg;
+                f.write_str(s.as_str())
+            }
+            ErrKind::Stalled(diagnostics) => {
+                let s = "Solver stalled: ".to_owned() + diagnostics.to_string().as_str();
+                f.write_str(s.as_str())
+            }
         }
     }
 }
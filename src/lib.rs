@@ -31,12 +31,23 @@ pub type DVec = DVector<f64>;
 pub type DMat = DMatrix<f64>;
 pub type FUN_nD_TO_1D = fn(&DVec) -> f64;
 
+/// Scalar type of the generic routines in `equation`, `matrix_utils` and `linesearch`
+/// (see the module docs for which ones are generic): f64 (the default for DVec, DMat and
+/// all optimizers) or f32 for cheap solves. Other nalgebra RealField types satisfy the
+/// bound but are untested.
+pub trait Real: nalgebra::RealField + Copy {}
+impl<T: nalgebra::RealField + Copy> Real for T {}
+
+/// The f64 constant x as a value of the scalar type T.
+pub(crate) fn real<T: Real>(x: f64) -> T { nalgebra::convert(x) }
+
 
 pub mod error;
 pub mod logging;
 pub mod matrix_utils;
 pub mod equation;
 pub mod optimization;
+pub mod sparse;
 pub mod test_problems;
 
 
@@ -0,0 +1,141 @@
+use nalgebra::{Dynamic, SymmetricEigen, linalg::{Cholesky, LU}};
+use rand_xoshiro::{Xoshiro256PlusPlus, rand_core::SeedableRng};
+use crate::{DVector, DMatrix, Real, real};
+use super::random_vector;
+
+
+
+/// The 1-norm $||A||_1$, i.e. the largest column sum of |a_ij|.
+pub fn norm_1<T: Real>(A: &DMatrix<T>) -> T {
+
+    A.column_iter().map(|c| c.iter().fold(T::zero(),|s,a| s+a.abs())).fold(T::zero(),T::max)
+}
+
+
+/// Hager/Higham estimate of $||A^{-1}||_1$ using only products with $A^{-1}$ and $A^{-T}$,
+/// i.e. solves with an existing factorization (O(n²) each, usually 4-5 of them).
+/// The estimate is a lower bound which is almost always within a factor 3 of the true value.
+///
+/// # Arguments
+///
+/// * `n`: dimension of A
+/// * `solve`: x -> A^{-1}x
+/// * `solve_transposed`: x -> A^{-T}x
+///
+pub fn estimate_inverse_norm_1<T: Real>(
+    n: usize, solve: &dyn Fn(&DVector<T>) -> DVector<T>, solve_transposed: &dyn Fn(&DVector<T>) -> DVector<T>
+) -> T {
+
+    if n == 0 { return T::zero(); }
+    let norm_1 = |v: &DVector<T>| v.iter().fold(T::zero(),|s,v_i| s+v_i.abs());
+
+    let mut x = DVector::repeat(n,real::<T>(1f64/(n as f64)));
+    let mut y = solve(&x);
+    let mut est = norm_1(&y);
+    let mut k = 0;
+    while k < 5 {
+
+        let xi = y.map(|y_i| if y_i >= T::zero() { T::one() } else { -T::one() });
+        let z = solve_transposed(&xi);
+        let j = z.iamax();
+        if k > 0 && z[j].abs() <= z.dot(&x) { break; }
+        x = DVector::zeros(n);
+        x[j] = T::one();
+        y = solve(&x);
+        let est_new = norm_1(&y);
+        if est_new <= est { break; }
+        est = est_new;
+        k += 1;
+    }
+    // Higham's safeguard against counterexamples to Hager's method
+    let b = DVector::from_fn(n,|i,_| {
+        let sign = if i%2 == 0 { 1f64 } else { -1f64 };
+        real::<T>(sign*(1f64+(i as f64)/((n.max(2)-1) as f64)))
+    });
+    est.max(real::<T>(2f64/(3f64*(n as f64)))*norm_1(&solve(&b)))
+}
+
+
+/// Estimate of the condition number $||A||_1||A^{-1}||_1$ of the symmetric positive definite
+/// matrix A from its Cholesky factorization, costs O(n²).
+///
+pub fn cholesky_condition_estimate<T: Real>(A: &DMatrix<T>, chol: &Cholesky<T,Dynamic>) -> T {
+
+    let solve = |x: &DVector<T>| chol.solve(x);
+    norm_1(A)*estimate_inverse_norm_1(A.nrows(),&solve,&solve)
+}
+
+
+/// Estimate of the condition number $||A||_1||A^{-1}||_1$ of the square matrix A from its
+/// LU factorization PA=LU, costs O(n²). Returns infinity if A is singular.
+///
+pub fn lu_condition_estimate<T: Real>(A: &DMatrix<T>, lu: &LU<T,Dynamic,Dynamic>) -> T {
+
+    let infinity: T = real(f64::INFINITY);
+    if !lu.is_invertible() { return infinity; }
+    let n = A.nrows();
+    let L = lu.l();
+    let U = lu.u();
+    let P = lu.p();
+    let nan = || DVector::repeat(n,real::<T>(f64::NAN));
+    let solve = |x: &DVector<T>| lu.solve(x).unwrap_or_else(nan);
+    // A' = U'L'P
+    let solve_transposed = |x: &DVector<T>| {
+        let w = U.tr_solve_upper_triangular(x).unwrap_or_else(nan);
+        let mut v = L.tr_solve_lower_triangular(&w).unwrap_or_else(nan);
+        P.inv_permute_rows(&mut v);
+        v
+    };
+    norm_1(A)*estimate_inverse_norm_1(n,&solve,&solve_transposed)
+}
+
+
+/// Estimates of the smallest and largest eigenvalue of the symmetric matrix A from k steps
+/// of the Lanczos method (with full reorthogonalization) started at a fixed random vector.
+/// Costs k matrix vector products. The estimates lie inside the spectrum, the largest
+/// eigenvalue converges fast, the smallest one more slowly if it is not well separated.
+///
+pub fn lanczos_extreme_eigenvalues<T: Real>(A: &DMatrix<T>, k: usize) -> (T,T) {
+
+    let n = A.nrows();
+    assert!(A.ncols()==n && n > 0,"matrix A not square or empty");
+    let k = k.min(n).max(1);
+
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(1);
+    let v0 = random_vector(n,-T::one(),T::one(),&mut rng);
+    let mut V: Vec<DVector<T>> = vec![v0.normalize()];
+    let mut alpha: Vec<T> = Vec::with_capacity(k);
+    let mut beta: Vec<T> = Vec::with_capacity(k);
+    let tol = real::<T>(1e-12)*A.amax();
+    while alpha.len() < k {
+
+        let j = alpha.len();
+        let mut w: DVector<T> = A*&V[j];
+        alpha.push(w.dot(&V[j]));
+        for v in V.iter() {
+            let c = w.dot(v);
+            w -= v*c;
+        }
+        let b = w.norm();
+        if alpha.len() == k || b <= tol { break; }
+        beta.push(b);
+        V.push(w/b);
+    }
+    let m = alpha.len();
+    let S = DMatrix::from_fn(m,m,|r,c|
+        if r == c { alpha[r] } else if r == c+1 { beta[c] } else if c == r+1 { beta[r] } else { T::zero() }
+    );
+    let theta = SymmetricEigen::new(S).eigenvalues;
+    (theta.min(),theta.max())
+}
+
+
+/// Lanczos estimate $\lambda_{max}/\lambda_{min}$ of the 2-norm condition number of the
+/// symmetric positive definite matrix A, see lanczos_extreme_eigenvalues. This is a lower
+/// bound on the true condition number.
+///
+pub fn lanczos_condition_estimate<T: Real>(A: &DMatrix<T>, k: usize) -> T {
+
+    let (l_min,l_max) = lanczos_extreme_eigenvalues(A,k);
+    if l_min > T::zero() { l_max/l_min } else { real(f64::INFINITY) }
+}
@@ -0,0 +1,211 @@
+use crate::{DVector, DMatrix, Real, real};
+use super::ruiz_equilibration;
+
+
+
+/// Symmetric scaling $B=DAD$, $D=diag(d)$, of a symmetric matrix A which makes the entries
+/// of B more uniform in size, hopefully reducing the condition number.
+/// See ruiz_equilibration for how this is used to solve equations.
+/// Generic over the scalar type T (f64 by default), all strategies implement it for every T.
+///
+pub trait Equilibration<T: Real = f64> {
+
+    fn id(&self) -> String;
+
+    /// the positive scaling vector d
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T>;
+
+    /// Returns tuple (d,B).
+    fn equilibrate(&self, A: &DMatrix<T>) -> (DVector<T>, DMatrix<T>) {
+        let d = self.scaling(A);
+        let B = scaled_matrix(A,&d);
+        (d,B)
+    }
+}
+
+
+/// The matrix B = diag(d) A diag(d).
+fn scaled_matrix<T: Real>(A: &DMatrix<T>, d: &DVector<T>) -> DMatrix<T> {
+
+    DMatrix::from_fn(A.nrows(),A.ncols(),|r,c| d[r]*d[c]*A[(r,c)])
+}
+
+
+/// Largest deviation of the row norms of B from one.
+fn max_deviation<T: Real>(row_norms: &DVector<T>) -> T {
+
+    row_norms.iter().filter(|&&s| s > T::zero()).map(|&s| (s-T::one()).abs()).fold(T::zero(),T::max)
+}
+
+
+
+/// No scaling, d = 1.
+pub struct NoEquilibration {}
+
+impl<T: Real> Equilibration<T> for NoEquilibration {
+
+    fn id(&self) -> String { String::from("none") }
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T> { DVector::repeat(A.nrows(),T::one()) }
+    fn equilibrate(&self, A: &DMatrix<T>) -> (DVector<T>, DMatrix<T>) { (self.scaling(A),A.clone()) }
+}
+
+
+/// Ruiz equilibration with a fixed number of rounds, see ruiz_equilibration.
+/// This is the scaling used by cholesky_solve_regularized and qr_solve (5 and 5 rounds).
+///
+pub struct Ruiz {
+    /// number of rounds of ||.||_oo equilibration
+    pub n_oo: usize,
+    /// number of rounds of ||.||_2 equilibration
+    pub n_2: usize,
+}
+
+impl Ruiz {
+    pub fn new(n_oo: usize, n_2: usize) -> Ruiz { Ruiz{ n_oo, n_2 } }
+}
+
+impl Default for Ruiz {
+    fn default() -> Ruiz { Ruiz::new(5,5) }
+}
+
+impl<T: Real> Equilibration<T> for Ruiz {
+
+    fn id(&self) -> String { format!("Ruiz({},{})",self.n_oo,self.n_2) }
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T> { ruiz_equilibration(A,self.n_oo,self.n_2).0 }
+    fn equilibrate(&self, A: &DMatrix<T>) -> (DVector<T>, DMatrix<T>) {
+        ruiz_equilibration(A,self.n_oo,self.n_2)
+    }
+}
+
+
+/// Ruiz ||.||_oo equilibration iterated until all rows of B have max norm within `tol`
+/// of one (the iteration converges linearly with rate 1/2) or `max_iter` rounds are done.
+///
+pub struct RuizConvergent {
+    pub tol: f64,
+    pub max_iter: usize,
+}
+
+impl RuizConvergent {
+    pub fn new(tol: f64, max_iter: usize) -> RuizConvergent { RuizConvergent{ tol, max_iter } }
+}
+
+impl<T: Real> Equilibration<T> for RuizConvergent {
+
+    fn id(&self) -> String { format!("Ruiz(tol={:e})",self.tol) }
+
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T> {
+
+        let n = A.nrows();
+        let tol: T = real(self.tol);
+        let mut d = DVector::repeat(n,T::one());
+        let mut B = A.clone();
+        let mut k = 0;
+        while k < self.max_iter {
+
+            let r = DVector::from_fn(n,|i,_| B.row(i).amax());
+            if max_deviation(&r) <= tol { break; }
+            for i in 0..n { if r[i] > T::zero() { d[i] /= r[i].sqrt(); } }
+            B = scaled_matrix(A,&d);
+            k += 1;
+        }
+        d
+    }
+}
+
+
+/// Jacobi scaling $d_i = 1/\sqrt{|a_{ii}|}$, B has unit diagonal (if A has no zero diagonal
+/// elements; rows with $a_{ii}=0$ are not scaled).
+///
+pub struct JacobiScaling {}
+
+impl<T: Real> Equilibration<T> for JacobiScaling {
+
+    fn id(&self) -> String { String::from("Jacobi") }
+
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T> {
+        DVector::from_fn(A.nrows(),|i,_| {
+            let a_ii = A[(i,i)].abs();
+            if a_ii > T::zero() { T::one()/a_ii.sqrt() } else { T::one() }
+        })
+    }
+}
+
+
+/// Symmetric Sinkhorn-Knopp scaling: iterates $d_i \leftarrow d_i/\sqrt{r_i}$, where $r_i$ is
+/// the 1-norm of row i of B, until B is doubly stochastic in absolute value up to `tol`
+/// or `max_iter` rounds are done.
+///
+pub struct SinkhornKnopp {
+    pub tol: f64,
+    pub max_iter: usize,
+}
+
+impl SinkhornKnopp {
+    pub fn new(tol: f64, max_iter: usize) -> SinkhornKnopp { SinkhornKnopp{ tol, max_iter } }
+}
+
+impl<T: Real> Equilibration<T> for SinkhornKnopp {
+
+    fn id(&self) -> String { String::from("Sinkhorn-Knopp") }
+
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T> {
+
+        let n = A.nrows();
+        let tol: T = real(self.tol);
+        let absA = A.map(|a| a.abs());
+        let mut d = DVector::repeat(n,T::one());
+        let mut k = 0;
+        while k < self.max_iter {
+
+            // row sums of |B| = diag(d)|A|diag(d)
+            let r: DVector<T> = d.component_mul(&(&absA*&d));
+            if max_deviation(&r) <= tol { break; }
+            for i in 0..n { if r[i] > T::zero() { d[i] /= r[i].sqrt(); } }
+            k += 1;
+        }
+        d
+    }
+}
+
+
+/// Geometric mean scaling: iterates $d_i \leftarrow d_i/\sqrt[4]{\max_j|b_{ij}|\min_j|b_{ij}|}$
+/// (min over the nonzero entries) for `n_iter` rounds, which centers the magnitudes of the
+/// entries of each row around one. Often better than max norm scaling for entries
+/// spanning many orders of magnitude.
+///
+pub struct GeometricMean {
+    pub n_iter: usize,
+}
+
+impl GeometricMean {
+    pub fn new(n_iter: usize) -> GeometricMean { GeometricMean{ n_iter } }
+}
+
+impl<T: Real> Equilibration<T> for GeometricMean {
+
+    fn id(&self) -> String { String::from("geometric mean") }
+
+    fn scaling(&self, A: &DMatrix<T>) -> DVector<T> {
+
+        let n = A.nrows();
+        let mut d = DVector::repeat(n,T::one());
+        let mut B = A.clone();
+        for _ in 0..self.n_iter {
+
+            for i in 0..n {
+                let (mut b_max,mut b_min): (T,Option<T>) = (T::zero(),None);
+                for &b in B.row(i).iter() {
+                    let b = b.abs();
+                    if b > T::zero() {
+                        b_max = b_max.max(b);
+                        b_min = Some(b_min.map_or(b,|m| m.min(b)));
+                    }
+                }
+                if let Some(b_min) = b_min { d[i] /= (b_max*b_min).sqrt().sqrt(); }
+            }
+            B = scaled_matrix(A,&d);
+        }
+        d
+    }
+}
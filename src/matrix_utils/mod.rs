@@ -1,27 +1,39 @@
+//! Random test matrices, equilibration and condition estimation,
+//! all generic over the scalar type (see crate::Real).
+
 use rand::{prelude::*,Rng};
 use rand_xoshiro::{Xoshiro256PlusPlus, rand_core::SeedableRng};
 use rand_distr::{StandardNormal};
-use crate::{DVec, DMat};
+use crate::{DVector, DMatrix, Real, real};
+
+pub use self::{
+    condition::*,
+    equilibration::*
+};
+
+mod condition;
+mod equilibration;
 
 
 
-/// A vector in $R^dim$ with uniformly random f64 components in [a,b).
-pub fn random_vector(dim:usize,a:f64,b:f64, rng: &mut impl Rng) -> DVec {
+/// A vector in $R^dim$ with uniformly random components in [a,b).
+pub fn random_vector<T: Real>(dim:usize,a:T,b:T, rng: &mut impl Rng) -> DVector<T> {
 
-    DVec::from_fn(dim, |_i,_| a+(b-a)*rng.gen::<f64>())
+    DVector::from_fn(dim, |_i,_| a+(b-a)*real::<T>(rng.gen::<f64>()))
 }
 
-/// An mxn matrix with uniformly random f64 components in [a,b).
-pub fn random_matrix(m:usize,n:usize,a:f64,b:f64, rng: &mut impl Rng) -> DMat {
+/// An mxn matrix with uniformly random components in [a,b).
+pub fn random_matrix<T: Real>(m:usize,n:usize,a:T,b:T, rng: &mut impl Rng) -> DMatrix<T> {
 
-    DMat::from_fn(m,n, |_r,_c| a+(b-a)*rng.gen::<f64>())
+    DMatrix::from_fn(m,n, |_r,_c| a+(b-a)*real::<T>(rng.gen::<f64>()))
 }
 
 /// A random nxn orthogonal matrix. The matrix will be distributed uniformly
 /// with respect to Haar measure on the orthogonal group O(n).
-pub fn random_orthogonal_matrix(n:usize, rng: &mut impl Rng) -> DMat {
+/// The scalar type T is inferred from the use of the result.
+pub fn random_orthogonal_matrix<T: Real>(n:usize, rng: &mut impl Rng) -> DMatrix<T> {
 
-    let a = DMat::from_fn(n,n, |_r,_c| StandardNormal.sample(rng));
+    let a = DMatrix::from_fn(n,n, |_r,_c| real::<T>(StandardNormal.sample(rng)));
     let qr_a = a.qr();
     qr_a.q()
 }
@@ -30,11 +42,11 @@ pub fn random_orthogonal_matrix(n:usize, rng: &mut impl Rng) -> DMat {
 /// U is a random orthogonal matrix and D the diagonal matrix with diag(D) = eigvals,
 /// the vector of prescribed eigen values.
 ///
-pub fn random_symmetric_matrix(eigvals:DVec, rng: &mut impl Rng) -> DMat {
+pub fn random_symmetric_matrix<T: Real>(eigvals:DVector<T>, rng: &mut impl Rng) -> DMatrix<T> {
 
     let n = eigvals.shape().1;
     let u = random_orthogonal_matrix(n,rng);
-    &u*(DMat::from_diagonal(&eigvals)*&u.transpose())
+    &u*(DMatrix::from_diagonal(&eigvals)*&u.transpose())
 }
 
 /// A random symmetric positive definite nxn matrix A with eigenvalues decreasing exponentially
@@ -43,21 +55,21 @@ pub fn random_symmetric_matrix(eigvals:DVec, rng: &mut impl Rng) -> DMat {
 /// examples of ill conditioned matrices so we can test the efficiency of preconditioners or
 /// the accuracy of equation solvers.
 ///
-pub fn random_psd_matrix(n:usize,l_min:f64,l_max:f64, rng: &mut impl Rng) -> DMat {
+pub fn random_psd_matrix<T: Real>(n:usize,l_min:T,l_max:T, rng: &mut impl Rng) -> DMatrix<T> {
 
     assert!(n>1);
-    let q = (l_max/l_min).ln() / ((n-1) as f64);
-    let eigvals: DVec = DVec::from_fn(n,|i:usize,_| l_max*(-((i as f64)*q)).exp());
+    let q = (l_max/l_min).ln() / real::<T>((n-1) as f64);
+    let eigvals: DVector<T> = DVector::from_fn(n,|i:usize,_| l_max*(-(real::<T>(i as f64)*q)).exp());
     let u = random_orthogonal_matrix(n,rng);
-    &u*(DMat::from_diagonal(&eigvals)*&u.transpose())
+    &u*(DMatrix::from_diagonal(&eigvals)*&u.transpose())
 }
 /// Cross product of the vectors u,v, i.e the (rank one) matrix (u_i*v_j)
 ///
-pub fn cross_product(u:&DVec,v:&DVec) -> DMat {
+pub fn cross_product<T: Real>(u:&DVector<T>,v:&DVector<T>) -> DMatrix<T> {
 
     let m = u.len();
     let n = v.len();
-    DMat::from_fn(m,n,|r,c| u[r]*v[c])
+    DMatrix::from_fn(m,n,|r,c| u[r]*v[c])
 }
 
 
@@ -78,30 +90,32 @@ pub fn cross_product(u:&DVec,v:&DVec) -> DMat {
 ///
 /// Returns tuple (d,B).
 ///
-pub fn ruiz_equilibration(A: &DMat,n_oo:usize,n_2:usize) -> (DVec, DMat){
+pub fn ruiz_equilibration<T: Real>(A: &DMatrix<T>,n_oo:usize,n_2:usize) -> (DVector<T>, DMatrix<T>){
 
     let n = A.shape().0;
     assert!(A.shape().1==n,"matrix A not square, rows={}, cols={}",n,A.shape().1);
 
-    let mut d: DVec = DVec::repeat(n,1f64);
+    let mut d: DVector<T> = DVector::repeat(n,T::one());
     let mut B = A.clone();
-    let mut i = 0;
+    let mut i: usize;
     let mut k = 0;
 
-    // 30 rounds of ||.||_oo equilibration
+    // n_oo rounds of ||.||_oo equilibration
     while k < n_oo {
+
+        i = 0;
         while i<n {
 
             let mut f_i = B.row(i).amax().sqrt();
-            if f_i > 0f64 { d[i] /= f_i; }
+            if f_i > T::zero() { d[i] /= f_i; }
             i += 1;
         }
-        B = DMat::from_fn(n,n,|r,c| d[r]*d[c]*A[(r,c)]);
+        B = DMatrix::from_fn(n,n,|r,c| d[r]*d[c]*A[(r,c)]);
         k += 1;
     }
 
 
-    // 30 rounds of ||.||_2 equilibration
+    // n_2 rounds of ||.||_2 equilibration
     k = 0;
     while k < n_2 {
 
@@ -109,11 +123,51 @@ pub fn ruiz_equilibration(A: &DMat,n_oo:usize,n_2:usize) -> (DVec, DMat){
         while i<n {
 
             let mut f_i = B.row(i).norm().sqrt();
-            if f_i > 0f64 { d[i] /= f_i; }
+            if f_i > T::zero() { d[i] /= f_i; }
             i += 1;
         }
-        B = DMat::from_fn(n,n,|r,c| d[r]*d[c]*A[(r,c)]);
+        B = DMatrix::from_fn(n,n,|r,c| d[r]*d[c]*A[(r,c)]);
         k += 1;
     }
     (d,B)
-}
\ No newline at end of file
+}
+
+
+/// Two-sided Ruiz equilibration of a rectangular m x n matrix A: computes $B=D_rAD_c$ with
+/// positive diagonal matrices $D_r=diag(d_r)$, $D_c=diag(d_c)$ such that all rows and columns
+/// of B have max norm close to one (each round divides row i by $\sqrt{||B_{i.}||_\infty}$ and
+/// column j by $\sqrt{||B_{.j}||_\infty}$).
+///
+/// Used for constraint matrices: the constraints Ax <= c are equivalent to Bu <= D_r c with
+/// x = D_c u, multipliers y of the scaled constraints correspond to multipliers D_r y of the
+/// original ones.
+///
+/// # Arguments
+///
+/// * `n_iter`: number of rounds
+///
+/// Returns tuple (d_r,d_c,B).
+///
+pub fn two_sided_ruiz_equilibration<T: Real>(A: &DMatrix<T>, n_iter: usize)
+-> (DVector<T>, DVector<T>, DMatrix<T>) {
+
+    let (m,n) = A.shape();
+    let mut d_r: DVector<T> = DVector::repeat(m,T::one());
+    let mut d_c: DVector<T> = DVector::repeat(n,T::one());
+    let mut B = A.clone();
+    let mut k = 0;
+    while k < n_iter {
+
+        for i in 0..m {
+            let f_i = B.row(i).amax();
+            if f_i > T::zero() { d_r[i] /= f_i.sqrt(); }
+        }
+        for j in 0..n {
+            let f_j = B.column(j).amax();
+            if f_j > T::zero() { d_c[j] /= f_j.sqrt(); }
+        }
+        B = DMatrix::from_fn(m,n,|r,c| d_r[r]*d_c[c]*A[(r,c)]);
+        k += 1;
+    }
+    (d_r,d_c,B)
+}
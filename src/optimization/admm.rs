@@ -0,0 +1,246 @@
+use std::fmt;
+use crate::{
+    error::ConvOptError, error::ErrKind,
+    Result, DVec, DMat,
+    optimization::{MinProblem, Region, LineSearch, GoldenSearch, solve_min_problem}
+};
+
+
+
+/// Problem    ? = argmin_{x,z} f(x)+g(z)  subject to  Ax+Bz = c,
+/// with f and g convex, solved with the alternating direction method of multipliers
+/// (ADMM). The dimensions of x and z are those of f and g, A and B have c.len() rows.
+///
+pub struct AdmmProblem<'a> {
+    pub f: &'a dyn MinProblem,
+    pub g: &'a dyn MinProblem,
+    pub A: DMat,
+    pub B: DMat,
+    pub c: DVec,
+}
+impl<'a> AdmmProblem<'a> {
+
+    pub fn new(
+        f: &'a dyn MinProblem, g: &'a dyn MinProblem, A: DMat, B: DMat, c: DVec
+    ) -> AdmmProblem<'a> {
+
+        assert!(A.nrows()==c.len() && B.nrows()==c.len(),
+                "A, B and c must have the same number of rows");
+        assert!(A.ncols()==f.dim() && B.ncols()==g.dim(),
+                "dimension mismatch: dim(f) = {}, cols(A) = {}, dim(g) = {}, cols(B) = {}",
+                f.dim(),A.ncols(),g.dim(),B.ncols()
+        );
+        AdmmProblem{ f, g, A, B, c }
+    }
+}
+
+
+/// Subproblem of an ADMM iteration:
+///     ? = argmin_y h(y) + (rho/2)||My-v||²,
+/// where h=f, M=A for the x-update and h=g, M=B for the z-update.
+/// The start point is the previous iterate (warm start).
+///
+pub struct AugmentedSubproblem<'a> {
+    pub id: String,
+    pub h: &'a dyn MinProblem,
+    pub M: &'a DMat,
+    pub v: DVec,
+    pub rho: f64,
+    pub start: DVec,
+}
+impl<'a> MinProblem for AugmentedSubproblem<'a> {
+
+    fn id(&self) -> String { self.id.clone() }
+    fn dim(&self) -> usize { self.h.dim() }
+    fn start_point(&self) -> DVec { self.start.clone() }
+    fn objective_fn(&self, y: &DVec) -> f64 {
+        self.h.objective_fn(y) + 0.5f64*self.rho*(self.M*y-&self.v).norm_squared()
+    }
+    fn gradient(&self, y: &DVec) -> DVec {
+        self.h.gradient(y) + self.rho*self.M.tr_mul(&(self.M*y-&self.v))
+    }
+    fn hessian(&self, y: &DVec) -> DMat {
+        self.h.hessian(y) + self.rho*self.M.tr_mul(self.M)
+    }
+    fn domain(&self) -> &dyn Region { self.h.domain() }
+}
+
+
+/// Solver for the x- and z-updates of ADMM. Implement this to plug in closed form
+/// or otherwise specialized solutions of the subproblems.
+///
+pub trait SubproblemSolver {
+
+    fn solve(&self, prob: &AugmentedSubproblem) -> Result<DVec>;
+}
+
+
+/// Default subproblem solver: solve_min_problem with the given line search and
+/// termination criteria. The default uses GoldenSearch, eps=1e-8 and max_iter=100.
+///
+pub struct NewtonSubproblemSolver {
+    pub line_search: Box<dyn LineSearch>,
+    pub eps: f64,
+    pub max_iter: usize,
+}
+impl NewtonSubproblemSolver {
+    pub fn new(eps: f64, max_iter: usize) -> NewtonSubproblemSolver {
+        NewtonSubproblemSolver{ line_search: Box::new(GoldenSearch::new(0.1)), eps, max_iter }
+    }
+}
+impl Default for NewtonSubproblemSolver {
+    fn default() -> NewtonSubproblemSolver { NewtonSubproblemSolver::new(1e-8,100) }
+}
+impl SubproblemSolver for NewtonSubproblemSolver {
+
+    fn solve(&self, prob: &AugmentedSubproblem) -> Result<DVec> {
+        solve_min_problem(prob, self.line_search.as_ref(), self.eps, self.max_iter)
+    }
+}
+
+
+/// Parameters of the ADMM iteration.
+///
+#[derive(Clone,Debug)]
+pub struct AdmmOptions {
+    /// initial penalty parameter
+    pub rho: f64,
+    /// over-relaxation parameter in (0,2), alpha=1: no relaxation,
+    /// values in [1.5,1.8] often speed up convergence
+    pub alpha: f64,
+    /// adapt rho by residual balancing
+    pub adaptive_rho: bool,
+    /// residual balancing: rho is changed when one residual exceeds mu times the other
+    pub mu: f64,
+    /// residual balancing: factor by which rho is changed
+    pub tau: f64,
+    /// absolute tolerance for the primal and dual residual
+    pub eps_abs: f64,
+    /// relative tolerance for the primal and dual residual
+    pub eps_rel: f64,
+    pub max_iter: usize,
+}
+impl Default for AdmmOptions {
+    fn default() -> AdmmOptions {
+        AdmmOptions{
+            rho: 1f64, alpha: 1.6f64, adaptive_rho: true, mu: 10f64, tau: 2f64,
+            eps_abs: 1e-6, eps_rel: 1e-4, max_iter: 1000
+        }
+    }
+}
+
+
+/// Result of the ADMM iteration.
+///
+#[derive(Debug)]
+pub struct AdmmResult {
+    pub x: DVec,
+    pub z: DVec,
+    /// scaled dual variable, the multiplier of the constraint Ax+Bz=c is rho*u
+    pub u: DVec,
+    /// final penalty parameter
+    pub rho: f64,
+    /// ||Ax+Bz-c||
+    pub primal_residual: f64,
+    /// ||rho*A'B(z-z_previous)||
+    pub dual_residual: f64,
+    pub iterations: usize,
+}
+
+impl fmt::Display for AdmmResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "iterations: {0}, rho: {1:.4}, primal residual: {2:.4e}, dual residual: {3:.4e}\n\
+            x: {4:.6}\nz: {5:.6}",
+            self.iterations, self.rho, self.primal_residual, self.dual_residual, self.x, self.z
+        ).as_str())
+    }
+}
+
+
+/// Solves the AdmmProblem min f(x)+g(z) subject to Ax+Bz=c with the (scaled form) ADMM
+/// iteration
+///     x_{k+1} = argmin f(x) + (rho/2)||Ax+Bz_k-c+u_k||²,
+///     z_{k+1} = argmin g(z) + (rho/2)||Ax_hat+Bz-c+u_k||²,
+///     u_{k+1} = u_k + Ax_hat+Bz_{k+1}-c,
+/// where Ax_hat = alpha*Ax_{k+1}+(1-alpha)(c-Bz_k) is the over-relaxed value of Ax_{k+1}.
+/// The x- and z-updates are delegated to x_solver and z_solver, by default (None)
+/// NewtonSubproblemSolver::default(), i.e. solve_min_problem.
+///
+/// The iteration stops when the primal residual r=Ax+Bz-c and the dual residual
+/// s=rho*A'B(z_{k+1}-z_k) satisfy
+///     ||r|| <= sqrt(m)*eps_abs + eps_rel*max(||Ax||,||Bz||,||c||),
+///     ||s|| <= sqrt(n)*eps_abs + eps_rel*||rho*A'u||,
+/// (m=rows of A, n=dim(x)). With adaptive_rho, rho is increased by the factor tau if
+/// ||r|| > mu*||s|| and decreased if ||s|| > mu*||r|| (residual balancing), u is rescaled
+/// accordingly.
+///
+/// The start points are the start points of f and g, u starts at 0.
+///
+pub fn admm(
+    prob: &AdmmProblem, x_solver: Option<&dyn SubproblemSolver>,
+    z_solver: Option<&dyn SubproblemSolver>, options: &AdmmOptions
+) -> Result<AdmmResult> {
+
+    assert!(0f64 < options.alpha && options.alpha < 2f64,"over-relaxation alpha not in (0,2)");
+    assert!(options.rho > 0f64);
+
+    let default_solver = NewtonSubproblemSolver::default();
+    let x_solver = x_solver.unwrap_or(&default_solver);
+    let z_solver = z_solver.unwrap_or(&default_solver);
+
+    let A = &prob.A;
+    let B = &prob.B;
+    let c = &prob.c;
+    let m = c.len();
+    let n = prob.f.dim();
+
+    let mut x = prob.f.start_point();
+    let mut z = prob.g.start_point();
+    let mut u = DVec::repeat(m,0f64);
+    let mut rho = options.rho;
+    let mut Bz = B*&z;
+
+    let mut iter = 0;
+    while iter < options.max_iter {
+
+        let x_prob = AugmentedSubproblem{
+            id: prob.f.id()+"_admm_x", h: prob.f, M: A, v: c-&Bz-&u, rho, start: x.clone()
+        };
+        x = x_solver.solve(&x_prob)?;
+        let Ax = A*&x;
+        let Ax_hat = options.alpha*&Ax+(1f64-options.alpha)*(c-&Bz);
+
+        let z_prob = AugmentedSubproblem{
+            id: prob.g.id()+"_admm_z", h: prob.g, M: B, v: c-&Ax_hat-&u, rho, start: z.clone()
+        };
+        z = z_solver.solve(&z_prob)?;
+        let Bz_next = B*&z;
+        u += &Ax_hat+&Bz_next-c;
+
+        let r = (&Ax+&Bz_next-c).norm();
+        let s = rho*A.tr_mul(&(&Bz_next-&Bz)).norm();
+        Bz = Bz_next;
+        iter += 1;
+
+        let eps_pri = (m as f64).sqrt()*options.eps_abs +
+            options.eps_rel*Ax.norm().max(Bz.norm()).max(c.norm());
+        let eps_dual = (n as f64).sqrt()*options.eps_abs +
+            options.eps_rel*rho*A.tr_mul(&u).norm();
+        if r <= eps_pri && s <= eps_dual {
+            return Ok(AdmmResult{
+                x, z, u, rho, primal_residual: r, dual_residual: s, iterations: iter
+            });
+        }
+        if options.adaptive_rho {
+            if r > options.mu*s {
+                rho *= options.tau;
+                u /= options.tau;
+            } else if s > options.mu*r {
+                rho /= options.tau;
+                u *= options.tau;
+            }
+        }
+    }
+    Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit in admm")))
+}
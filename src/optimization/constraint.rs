@@ -5,7 +5,8 @@ use crate::{
     Result, DVec, DMat, FUN_nD_TO_1D,
     logging::Logger,
     optimization::{MinProblem, golden_search},
-    matrix_utils::cross_product
+    matrix_utils::cross_product,
+    sparse::{CscMatrix, sparse_two_sided_ruiz_equilibration}
 };
 
 use super::Region;
@@ -47,6 +48,8 @@ pub trait InequalityConstraint {
         r*(self.hessian(x)-r*cross_product(&g,&g))
     }
     fn clone_self(&self) -> Box<dyn InequalityConstraint>;
+    /// Some(self) if this is a LinearInequalityConstraint.
+    fn as_linear(&self) -> Option<&LinearInequalityConstraint> { None }
 }
 
 
@@ -107,6 +110,7 @@ impl InequalityConstraint for LinearInequalityConstraint {
         DMat::from_element(self.dim(),self.dim(),0f64)
     }
     fn clone_self(&self) -> Box<dyn InequalityConstraint> { Box::new(self.clone() ) }
+    fn as_linear(&self) -> Option<&LinearInequalityConstraint> { Some(self) }
 }
 
 
@@ -186,6 +190,43 @@ impl ConstraintSet {
         self.constraints.iter().
             map(|ct: &Box<dyn InequalityConstraint>| -> DMat { ct.log_barrier_hessian(x) }).sum()
     }
+    /// If all constraints are linear, a_i'x <= c_i, the sparse constraint matrix A with rows
+    /// a_i' and the vector c, so that the constraints read Ax <= c. None otherwise.
+    pub fn linear_constraint_matrix(&self) -> Option<(CscMatrix,DVec)> {
+
+        let m = self.constraints.len();
+        let mut triplets: Vec<(usize,usize,f64)> = Vec::new();
+        let mut c = DVec::repeat(m,0f64);
+        for (i,ct) in self.constraints.iter().enumerate() {
+
+            let lin = ct.as_linear()?;
+            for (j,a) in lin.a.iter().enumerate() {
+                if *a != 0f64 { triplets.push((i,j,*a)); }
+            }
+            c[i] = lin.c;
+        }
+        Some((CscMatrix::from_triplets(m,self.dim,&triplets),c))
+    }
+    /// If all constraints are linear, Ax <= c, the equivalent scaled constraint set
+    /// $D_rAD_cu \leq D_rc$ in the variables $u=D_c^{-1}x$, where $D_rAD_c$ is the two-sided
+    /// Ruiz equilibration of A with `n_iter` rounds. None otherwise.
+    /// The result maps primal and dual solutions between the two problems.
+    pub fn equilibrate(&self, n_iter: usize) -> Option<ScaledConstraintSet> {
+
+        let (A,c) = self.linear_constraint_matrix()?;
+        let (d_r,d_c,B) = sparse_two_sided_ruiz_equilibration(&A,n_iter);
+        let Bt = B.transpose();   // column i = row i of B
+        let mut constraints = ConstraintSet::new(String::from("scaled ")+self.id.as_str(),self.dim);
+        for (i,ct) in self.constraints.iter().enumerate() {
+
+            let mut a = DVec::repeat(self.dim,0f64);
+            for k in Bt.col_ptr[i]..Bt.col_ptr[i+1] { a[Bt.row_idx[k]] = Bt.values[k]; }
+            constraints.add_constraint(Box::new(
+                LinearInequalityConstraint::new(ct.id()+" (scaled)",a,d_r[i]*c[i])
+            ));
+        }
+        Some(ScaledConstraintSet{ constraints, d_r, d_c })
+    }
     /// the set of feasibility constraints g(x)-r <= 0 for all constraints g(x) <= 0
     /// in this constraint set.
     /// Needed for phase I feasibility analysis
@@ -205,6 +246,35 @@ impl ConstraintSet {
         res
     }
 }
+
+
+/// Linear constraint set $Ax\leq c$ scaled to $D_rAD_cu\leq D_rc$, see
+/// ConstraintSet::equilibrate. A problem min f(x) subject to $Ax\leq c$ is solved as
+/// min f(D_c u) subject to the scaled constraints, the scaled solution (u,y) maps back
+/// to the solution $x=D_cu$ with multipliers $D_ry$ of the original problem.
+///
+pub struct ScaledConstraintSet {
+    /// the scaled constraints, in the variables u
+    pub constraints: ConstraintSet,
+    /// row scaling D_r
+    pub d_r: DVec,
+    /// column scaling D_c
+    pub d_c: DVec,
+}
+
+impl ScaledConstraintSet {
+
+    /// u = D_c^{-1}x
+    pub fn scale_primal(&self, x: &DVec) -> DVec { x.component_div(&self.d_c) }
+    /// x = D_c u
+    pub fn unscale_primal(&self, u: &DVec) -> DVec { u.component_mul(&self.d_c) }
+    /// multipliers of the scaled constraints from multipliers y of the original ones: D_r^{-1}y
+    pub fn scale_dual(&self, y: &DVec) -> DVec { y.component_div(&self.d_r) }
+    /// multipliers of the original constraints from multipliers y of the scaled ones: D_r y
+    pub fn unscale_dual(&self, y: &DVec) -> DVec { y.component_mul(&self.d_r) }
+}
+
+
 impl Region for ConstraintSet {
 
     fn id(&self) -> String {
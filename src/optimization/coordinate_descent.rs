@@ -0,0 +1,185 @@
+use std::fmt;
+use rand::seq::SliceRandom;
+use rand_xoshiro::{Xoshiro256PlusPlus, rand_core::SeedableRng};
+use crate::{
+    error::ConvOptError, error::ErrKind,
+    Result, DVec,
+    optimization::{MinProblem, LineSearch}
+};
+
+
+
+/// Order in which the blocks of variables are visited in each epoch of coordinate descent.
+///
+#[derive(Clone,Debug)]
+pub enum CoordinateOrder {
+    /// blocks in the given order
+    Cyclic,
+    /// a new random permutation of the blocks in every epoch, generated by a
+    /// Xoshiro256PlusPlus generator with the given seed (reproducible)
+    Randomized(u64),
+}
+
+
+/// How the objective function is minimized over a block of variables.
+///
+pub enum CoordinateUpdate {
+    /// Exact minimization along each coordinate x_i of the block in turn by the line search
+    /// on the interval [x_i-radius, x_i+radius] (shortened to stay in the domain).
+    Exact{ line_search: Box<dyn LineSearch>, radius: f64 },
+    /// Newton step d_i = -(df/dx_i)/H_ii for all coordinates i in the block using only
+    /// the diagonal entries H_ii of the Hessian, followed by backtracking until the
+    /// objective function decreases.
+    DiagonalNewton,
+}
+
+
+/// Result of coordinate descent.
+///
+#[derive(Debug)]
+pub struct CoordinateDescentResult {
+    /// the minimizer computed
+    pub x: DVec,
+    /// objective function value at x
+    pub objF_x: f64,
+    /// ||grad(f)(x)||
+    pub norm_gradient: f64,
+    /// number of passes over all blocks
+    pub epochs: usize,
+}
+
+impl fmt::Display for CoordinateDescentResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "epochs: {0}, f(x): {1:.8}, ||grad(f)(x)||: {2:.4e}\nx: {3:.6}",
+            self.epochs, self.objF_x, self.norm_gradient, self.x
+        ).as_str())
+    }
+}
+
+
+/// The blocks {0},{1},...,{dim-1} of single coordinates.
+///
+pub fn singleton_blocks(dim: usize) -> Vec<Vec<usize>> {
+
+    (0..dim).map(|i| vec![i]).collect()
+}
+
+
+/// Minimizes the objective function f of `min_prob` by block coordinate descent: in each
+/// epoch the blocks of variables are visited in the given order and f is minimized (or
+/// decreased) over the variables in the current block with all other variables fixed.
+///
+/// Coordinate descent needs only partial derivatives and diagonal Hessian entries, so
+/// problems with separable structure should override MinProblem::partial_derivative and
+/// MinProblem::hessian_diagonal. The full gradient is computed once per epoch to check
+/// the termination criterion.
+///
+/// # Arguments
+///
+/// * `blocks`: partition of the coordinates {0,...,dim-1} into blocks,
+///   e.g. singleton_blocks(dim).
+/// * `order`: order in which the blocks are visited.
+/// * `update`: minimization over a block.
+/// * `eps`: termination criterion ||grad(f)(x)|| < eps*sqrt(dim).
+/// * `max_epochs`: maximal number of passes over all blocks.
+///
+pub fn coordinate_descent(
+    min_prob: &dyn MinProblem, blocks: &[Vec<usize>], order: &CoordinateOrder,
+    update: &CoordinateUpdate, eps: f64, max_epochs: usize
+) -> Result<CoordinateDescentResult> {
+
+    let G = min_prob.domain();
+    let rho = eps*(min_prob.dim() as f64).sqrt();
+    let mut x = min_prob.start_point();
+    assert!(G.contains(&x),"start point not in domain");
+
+    let mut rng = match order {
+        CoordinateOrder::Randomized(seed) => Some(Xoshiro256PlusPlus::seed_from_u64(*seed)),
+        CoordinateOrder::Cyclic => None
+    };
+    let mut permutation: Vec<usize> = (0..blocks.len()).collect();
+
+    let mut epoch = 0;
+    let mut g = min_prob.gradient(&x);
+    while g.norm() >= rho {
+
+        if epoch == max_epochs {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max epochs hit")));
+        }
+        if let Some(rng) = rng.as_mut() { permutation.shuffle(rng); }
+        for &k in permutation.iter() {
+
+            let block = &blocks[k];
+            match update {
+                CoordinateUpdate::Exact{ line_search, radius } => {
+                    for &i in block.iter() {
+                        x = coordinate_line_search(min_prob,&x,i,line_search.as_ref(),*radius);
+                    }
+                },
+                CoordinateUpdate::DiagonalNewton => {
+                    x = diagonal_newton_block_step(min_prob,&x,block);
+                }
+            }
+        }
+        g = min_prob.gradient(&x);
+        epoch += 1;
+    }
+    Ok(CoordinateDescentResult{
+        objF_x: min_prob.objective_fn(&x), norm_gradient: g.norm(), x, epochs: epoch
+    })
+}
+
+
+/// Minimizes f along the coordinate x_i on the interval [x_i-r,x_i+r] pulled back
+/// into the domain. Returns the new point, x itself if there is no decrease.
+///
+fn coordinate_line_search(
+    min_prob: &dyn MinProblem, x: &DVec, i: usize, line_search: &dyn LineSearch, r: f64
+) -> DVec {
+
+    let G = min_prob.domain();
+    let mut e_i = DVec::repeat(x.len(),0f64);
+    e_i[i] = 1f64;
+    // feasible interval [a,b] for the step t
+    let a = (G.retract(x,&(x-r*&e_i))-x)[i];
+    let b = (G.retract(x,&(x+r*&e_i))-x)[i];
+    if b-a <= 0f64 { return x.clone(); }
+
+    let phi = |t: f64| min_prob.objective_fn(&(x+t*&e_i));
+    let ls_result = line_search.minimize(&phi,a,b);
+    if ls_result.f_t < min_prob.objective_fn(x) { x+ls_result.t*&e_i } else { x.clone() }
+}
+
+
+/// Newton step d_i=-g_i/H_ii on the coordinates i in the block, shortened by halving
+/// until the point is in the domain and satisfies the Armijo condition.
+/// Falls back to d_i=-g_i if H_ii <= 0. Returns the new point, x itself if no
+/// decrease was found.
+///
+fn diagonal_newton_block_step(min_prob: &dyn MinProblem, x: &DVec, block: &[usize]) -> DVec {
+
+    let G = min_prob.domain();
+    let h = min_prob.hessian_diagonal(x);
+    let mut d = DVec::repeat(x.len(),0f64);
+    let mut slope = 0f64;
+    for &i in block.iter() {
+
+        let g_i = min_prob.partial_derivative(x,i);
+        d[i] = if h[i] > 0f64 { -g_i/h[i] } else { -g_i };
+        slope += g_i*d[i];
+    }
+    if slope >= 0f64 { return x.clone(); }
+
+    let fx = min_prob.objective_fn(x);
+    let mut t = 1f64;
+    let mut k = 0;
+    while k < 50 {
+
+        let z = x+t*&d;
+        if G.contains(&z) && min_prob.objective_fn(&z) <= fx+1e-4*t*slope { return z; }
+        t /= 2f64;
+        k += 1;
+    }
+    x.clone()
+}
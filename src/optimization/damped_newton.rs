@@ -0,0 +1,111 @@
+use std::fmt;
+use crate::{
+    error::ConvOptError, error::ErrKind,
+    Result, DVec,
+    equation::cholesky_solve_regularized,
+    optimization::{MinProblem, backtracking_search},
+    sparse::SymbolicCholesky
+};
+
+
+
+/// Result of the damped Newton method.
+///
+#[derive(Debug)]
+pub struct DampedNewtonResult {
+    /// the minimizer computed
+    pub x: DVec,
+    /// objective function value at x
+    pub objF_x: f64,
+    /// half the squared Newton decrement lambda(x)²/2 = g'H^{-1}g/2 at x, this is
+    /// an estimate of f(x)-min(f) (exact for quadratic f)
+    pub half_decrement_squared: f64,
+    /// number of Newton steps taken
+    pub iterations: usize,
+}
+
+impl fmt::Display for DampedNewtonResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "iterations: {0}, f(x): {1:.8}, lambda(x)²/2: {2:.4e}\nx: {3:.6}",
+            self.iterations, self.objF_x, self.half_decrement_squared, self.x
+        ).as_str())
+    }
+}
+
+
+
+/// Minimizes the objective function f of `min_prob` with the classical damped Newton method:
+/// at the current iterate x the Newton direction p is the solution of Hp=-g and we move
+/// to x+tp where t is determined by backtracking line search with the Armijo condition,
+/// keeping all iterates in the domain of the problem.
+///
+/// The iteration stops when lambda(x)²/2 <= eps, where lambda(x)² = g'H^{-1}g = -g'p is the
+/// squared Newton decrement. Unlike the criterion ||grad(f)(x)|| < eps*sqrt(dim) used in
+/// solve_min_problem this is invariant under affine changes of coordinates and so
+/// insensitive to the scaling of the problem. For self-concordant f (e.g. barrier subproblems of
+/// self-concordant constraints) the number of Newton steps is bounded by
+/// (f(x_0)-min(f))/gamma + log2(log2(1/eps)), gamma = alpha*beta*(1-2*alpha)²/(20-8*alpha).
+///
+/// The Hessian H must be positive definite at all iterates, otherwise the Cholesky
+/// factorization of H fails and so does the method (no shift is added to H since -g'p would
+/// then no longer be the Newton decrement). If the problem provides a sparse Hessian
+/// (MinProblem::sparse_hessian) the Newton equation is solved by sparse Cholesky
+/// factorization, the symbolic factorization is computed once and reused as long as the
+/// sparsity pattern does not change, else by cholesky_solve_regularized.
+///
+/// # Arguments
+///
+/// * `alpha`: Armijo parameter in (0,1/2), typically 0.01-0.3.
+/// * `beta`: backtracking factor in (0,1), typically 0.1-0.8.
+/// * `eps`: termination criterion lambda(x)²/2 <= eps.
+/// * `max_iter`: maximal number of Newton steps.
+///
+pub fn damped_newton(
+    min_prob: &dyn MinProblem, alpha: f64, beta: f64, eps: f64, max_iter: usize
+) -> Result<DampedNewtonResult> {
+
+    let G = min_prob.domain();
+    let f = |z: &DVec| -> f64 {
+        if G.contains(z) { min_prob.objective_fn(z) } else { f64::INFINITY }
+    };
+    let mut x = min_prob.start_point();
+    assert!(G.contains(&x),"start point not in region G = {}",G.id());
+    let mut fx = f(&x);
+
+    let mut symbolic: Option<SymbolicCholesky> = None;
+    let mut iter = 0;
+    loop {
+        let g = min_prob.gradient(&x);
+        let p = match min_prob.sparse_hessian(&x) {
+            Some(H) => {
+                if !symbolic.as_ref().is_some_and(|s| s.matches_pattern(&H)) {
+                    symbolic = Some(SymbolicCholesky::analyze(&H));
+                }
+                symbolic.as_ref().unwrap().factor(&H,0f64)?.solve(&(-&g))
+            },
+            None => cholesky_solve_regularized(&min_prob.hessian(&x),&(-&g),0f64)?
+        };
+        // squared Newton decrement
+        let lambda_sq = -g.dot(&p);
+
+        if 0.5f64*lambda_sq <= eps {
+            return Ok(DampedNewtonResult{
+                x, objF_x: fx, half_decrement_squared: 0.5f64*lambda_sq, iterations: iter
+            });
+        }
+        if iter == max_iter {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit")));
+        }
+        let phi = |t: f64| f(&(&x+t*&p));
+        let ls_result = backtracking_search(&phi,fx,-lambda_sq,alpha,beta,60);
+        if !ls_result.converged {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure(
+                "backtracking line search failed in damped_newton"
+            )));
+        }
+        x += ls_result.t*&p;
+        fx = ls_result.f_t;
+        iter += 1;
+    }
+}
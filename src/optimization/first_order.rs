@@ -0,0 +1,251 @@
+use std::fmt;
+use crate::{
+    error::ConvOptError, error::ErrKind,
+    Result, DVec,
+    optimization::{MinProblem, backtracking_search}
+};
+
+
+
+//------------------ First order methods ------------------//
+//
+// The methods in this module only use the objective function and the gradient of a
+// MinProblem, the Hessian is never evaluated. This makes them applicable to problems
+// in high dimensions where the dense Hessian cannot be formed.
+// Iterates are kept in the domain of the problem: points outside the domain are treated
+// as having objective function value +oo.
+
+
+/// Result of a first order minimization method.
+///
+#[derive(Debug)]
+pub struct FirstOrderResult {
+    /// the minimizer computed
+    pub x: DVec,
+    /// objective function value at x
+    pub objF_x: f64,
+    /// ||grad(f)(x)||
+    pub norm_gradient: f64,
+    /// number of iterations
+    pub iterations: usize,
+    /// number of restarts of the momentum (accelerated methods only)
+    pub restarts: usize,
+    /// final estimate of the Lipschitz constant of the gradient
+    pub lipschitz_estimate: f64,
+}
+
+impl fmt::Display for FirstOrderResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "iterations: {0}, restarts: {1}, f(x): {2:.8}, ||grad(f)(x)||: {3:.4e}, \
+            Lipschitz estimate: {4:.4e}\nx: {5:.6}",
+            self.iterations, self.restarts, self.objF_x, self.norm_gradient,
+            self.lipschitz_estimate, self.x
+        ).as_str())
+    }
+}
+
+
+/// The objective function of min_prob extended by +oo outside the domain.
+///
+fn extended_objective_fn(min_prob: &dyn MinProblem, x: &DVec) -> f64 {
+
+    if min_prob.domain().contains(x) { min_prob.objective_fn(x) } else { f64::INFINITY }
+}
+
+
+/// Initial estimate ||g(x-s*g)-g(x)||/(s*||g||) of the Lipschitz constant of the
+/// gradient at x from a small step s along the negative gradient g=g(x).
+///
+fn initial_lipschitz_estimate(min_prob: &dyn MinProblem, x: &DVec, g: &DVec) -> f64 {
+
+    let norm_g = g.norm();
+    if norm_g == 0f64 { return 1f64; }
+    let G = min_prob.domain();
+    let mut s = 1e-4f64*(1f64+x.norm())/norm_g;
+    let mut z = x-s*g;
+    while !G.contains(&z) && s > 1e-16 { s /= 2f64; z = x-s*g; }
+    let L = (min_prob.gradient(&z)-g).norm()/(s*norm_g);
+    if L.is_finite() && L > 0f64 { L } else { 1f64 }
+}
+
+
+/// Gradient step x+ = y-g/L from the point y with gradient g, where the Lipschitz estimate L
+/// is doubled until the sufficient decrease condition
+///     f(x+) <= f(y)-||g||²/(2L)
+/// holds (backtracking). Returns (x+,f(x+),L) or None if no such L was found.
+///
+fn lipschitz_gradient_step(
+    min_prob: &dyn MinProblem, y: &DVec, f_y: f64, g: &DVec, L: f64
+) -> Option<(DVec,f64,f64)> {
+
+    let norm_g_sq = g.norm_squared();
+    let mut L = L;
+    let mut k = 0;
+    while k < 100 {
+
+        let x = y-(1f64/L)*g;
+        let f_x = extended_objective_fn(min_prob,&x);
+        if f_x <= f_y-0.5f64*norm_g_sq/L { return Some((x,f_x,L)); }
+        L *= 2f64;
+        k += 1;
+    }
+    None
+}
+
+
+/// Minimizes the objective function f of `min_prob` by gradient descent: at the current
+/// iterate x we move in the direction p=-g/L, where g is the gradient at x and L an estimate
+/// of the Lipschitz constant of the gradient. The step size t in (0,1] is determined by
+/// backtracking line search with the Armijo condition. The Lipschitz estimate is then
+/// updated from the step actually taken.
+///
+/// # Arguments
+///
+/// * `alpha`: Armijo parameter in (0,1/2).
+/// * `beta`: backtracking factor in (0,1).
+/// * `eps`: termination criterion ||grad(f)(x)|| < eps*sqrt(dim).
+/// * `max_iter`: maximal number of iterations.
+///
+pub fn gradient_descent(
+    min_prob: &dyn MinProblem, alpha: f64, beta: f64, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    let rho = eps*(min_prob.dim() as f64).sqrt();
+    let mut x = min_prob.start_point();
+    assert!(min_prob.domain().contains(&x),"start point not in domain");
+    let mut fx = min_prob.objective_fn(&x);
+    let mut g = min_prob.gradient(&x);
+    let mut L = initial_lipschitz_estimate(min_prob,&x,&g);
+
+    let mut iter = 0;
+    while g.norm() >= rho {
+
+        if iter == max_iter {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit")));
+        }
+        let p = (-1f64/L)*&g;
+        let phi = |t: f64| extended_objective_fn(min_prob,&(&x+t*&p));
+        let ls_result = backtracking_search(&phi,fx,-g.norm_squared()/L,alpha,beta,60);
+        if !ls_result.converged {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure(
+                "backtracking line search failed in gradient_descent"
+            )));
+        }
+        // the step taken was t/L, if the full step was accepted try a longer one next time
+        L = if ls_result.t == 1f64 { L/2f64 } else { L/ls_result.t };
+        x += ls_result.t*&p;
+        fx = ls_result.f_t;
+        g = min_prob.gradient(&x);
+        iter += 1;
+    }
+    Ok(FirstOrderResult{
+        x, objF_x: fx, norm_gradient: g.norm(), iterations: iter,
+        restarts: 0, lipschitz_estimate: L
+    })
+}
+
+
+/// Nesterov's accelerated gradient method with adaptive restart:
+///     x_{k+1} = y_k - g(y_k)/L,  y_{k+1} = x_{k+1} + beta_k*(x_{k+1}-x_k),
+/// where the momentum beta_k = (theta_k-1)/theta_{k+1} follows the usual sequence
+/// theta_{k+1} = (1+sqrt(1+4*theta_k²))/2.
+///
+/// The momentum is reset (theta=1, y=x) whenever it points uphill, i.e.
+/// g(y_k)'(x_{k+1}-x_k) > 0, or y leaves the domain of the problem (gradient restart of
+/// O'Donoghue and Candès). This recovers linear convergence on strongly convex problems without
+/// knowledge of the modulus of convexity. The Lipschitz estimate L is found by backtracking
+/// and decreased slightly in every iteration so that it can adapt to the local curvature.
+///
+/// # Arguments
+///
+/// * `eps`: termination criterion ||grad(f)(y)|| < eps*sqrt(dim).
+/// * `max_iter`: maximal number of iterations.
+///
+pub fn nesterov_descent(
+    min_prob: &dyn MinProblem, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    accelerated_gradient(min_prob, true, eps, max_iter)
+}
+
+
+/// FISTA (fast iterative shrinkage thresholding algorithm of Beck and Teboulle) applied to
+/// a smooth problem, i.e. Nesterov's accelerated gradient method without restarts and a
+/// nondecreasing Lipschitz estimate L found by backtracking. This has the guaranteed
+/// convergence rate f(x_k)-min(f) <= 2*L*||x_0-x*||²/(k+1)².
+///
+/// # Arguments
+///
+/// * `eps`: termination criterion ||grad(f)(y)|| < eps*sqrt(dim).
+/// * `max_iter`: maximal number of iterations.
+///
+pub fn fista(
+    min_prob: &dyn MinProblem, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    accelerated_gradient(min_prob, false, eps, max_iter)
+}
+
+
+/// Accelerated gradient iteration shared by nesterov_descent (adaptive = true:
+/// restarts and decreasing Lipschitz estimate) and fista (adaptive = false).
+///
+fn accelerated_gradient(
+    min_prob: &dyn MinProblem, adaptive: bool, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    let G = min_prob.domain();
+    let rho = eps*(min_prob.dim() as f64).sqrt();
+    let mut x = min_prob.start_point();
+    assert!(G.contains(&x),"start point not in domain");
+    let mut y = x.clone();
+    let mut f_y = min_prob.objective_fn(&y);
+    let mut g_y = min_prob.gradient(&y);
+    let mut L = initial_lipschitz_estimate(min_prob,&y,&g_y);
+    let mut theta = 1f64;
+    let mut restarts = 0;
+
+    let mut iter = 0;
+    while g_y.norm() >= rho {
+
+        if iter == max_iter {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit")));
+        }
+        if adaptive { L *= 0.9f64; }
+        let (x_next,_,L_next) = match lipschitz_gradient_step(min_prob,&y,f_y,&g_y,L) {
+            Some(step) => step,
+            None => return Err(ConvOptError::new(ErrKind::ConvergenceFailure(
+                "no Lipschitz estimate found in accelerated gradient step"
+            )))
+        };
+        L = L_next;
+        let theta_next = 0.5f64*(1f64+(1f64+4f64*theta*theta).sqrt());
+        let dx = &x_next-&x;
+        let y_next = &x_next+((theta-1f64)/theta_next)*&dx;
+
+        if adaptive && (g_y.dot(&dx) > 0f64 || !G.contains(&y_next)) {
+
+            // restart: momentum points uphill
+            theta = 1f64;
+            y = x_next.clone();
+            restarts += 1;
+        } else if !G.contains(&y_next) {
+
+            theta = 1f64;
+            y = x_next.clone();
+        } else {
+
+            theta = theta_next;
+            y = y_next;
+        }
+        x = x_next;
+        f_y = min_prob.objective_fn(&y);
+        g_y = min_prob.gradient(&y);
+        iter += 1;
+    }
+    Ok(FirstOrderResult{
+        x: y, objF_x: f_y, norm_gradient: g_y.norm(), iterations: iter,
+        restarts, lipschitz_estimate: L
+    })
+}
@@ -1,17 +1,21 @@
+//! One dimensional minimization. All searches are generic over the scalar type
+//! (see crate::Real), the optimizers use the f64 instances.
+
 use crate::{
     error::ConvOptError, error::ErrKind,
     equation::cholesky_solve_regularized,
-    Result, DVec, DMat, FUN_nD_TO_1D,
+    Result, DVec, DMat, FUN_nD_TO_1D, Real, real,
     logging::Logger,
     optimization::MinProblem
 };
+use std::cell::Cell;
 
 /// Minimizes the quadratic polynomial interpolating the points
 /// (a,fa), (b,fb) and (c,fc), returns the point (u,f(u)) at which
 /// the minimum of f occurs among the points examined.
 ///
-pub fn poly2min<F>(f: &F, a:f64, fa:f64, b:f64, fb:f64, c:f64, fc:f64) -> (f64,f64)
-where F: Fn(f64) -> f64
+pub fn poly2min<T: Real, F>(f: &F, a:T, fa:T, b:T, fb:T, c:T, fc:T) -> (T,T)
+where F: Fn(T) -> T
 {
     // current minimum
     let (q,fq) =
@@ -19,12 +23,12 @@ where F: Fn(f64) -> f64
         if fb<=fa.min(fc) { (b,fb) } else { (c,fc) };
 
     // docs/PD.pdf, p20, eq(1.37)
-    let N: f64 = fa*(b*b-c*c) + fb*(c*c-a*a) + fc*(a*a-b*b);
-    let D: f64 = fa*(b-c) + fb*(c-a) + fc*(a-b);
-    let u: f64 = 0.5f64*(N/D);
+    let N: T = fa*(b*b-c*c) + fb*(c*c-a*a) + fc*(a*a-b*b);
+    let D: T = fa*(b-c) + fb*(c-a) + fc*(a-b);
+    let u: T = real::<T>(0.5)*(N/D);
 
     // D <= 0: quadratic polynomial has a max not a min
-    if D<=0f64 || u<a || u> b { (q,fq) } else {
+    if D<=T::zero() || u<a || u> b { (q,fq) } else {
 
         let fu = f(u);
         if fu <= fq { (u,fu) } else { (q,fq) }
@@ -48,15 +52,15 @@ where F: Fn(f64) -> f64
 ///
 /// This function is used to run the recursion in the golden search below.
 ///
-pub fn golden_search_rec<F>(
-    f: &F, a: f64, fa: f64, b: f64, fb: f64, c:f64, fc: f64, eps: f64) -> (f64,f64,f64,f64,f64,f64)
-where F: Fn(f64) -> f64
+pub fn golden_search_rec<T: Real, F>(
+    f: &F, a: T, fa: T, b: T, fb: T, c:T, fc: T, eps: T) -> (T,T,T,T,T,T)
+where F: Fn(T) -> T
 {
     assert!(a<c && c<b);
 
     if b-a<=eps { (a,fa,b,fb,c,fc) } else {
 
-        let rho = (5f64.sqrt()-1f64)/2f64;
+        let rho: T = real((5f64.sqrt()-1f64)/2f64);
         if b-c > c-a {  // split the interval [c,b]
 
             let d = b-rho*(b-c);
@@ -103,11 +107,11 @@ where F: Fn(f64) -> f64
 /// Returns: (u,fu) where u is the local or global minimizer of f on [a,b]
 /// computed.
 ///
-pub fn golden_search<F>(
-    f: &F, a: f64, b: f64, eps: f64) -> (f64,f64)
-    where F: Fn(f64) -> f64
+pub fn golden_search<T: Real, F>(
+    f: &F, a: T, b: T, eps: T) -> (T,T)
+    where F: Fn(T) -> T
 {
-    let rho = (5f64.sqrt()-1f64)/2f64;
+    let rho: T = real((5f64.sqrt()-1f64)/2f64);
     let fa = f(a);
     let fb = f(b);
     let c = b-rho*(b-a);
@@ -115,3 +119,216 @@ pub fn golden_search<F>(
     let gs = golden_search_rec(f,a,fa,b,fb,c,fc,eps);
     poly2min(f,gs.0,gs.1,gs.2,gs.3,gs.4,gs.5)
 }
+
+
+/// Attempts to locate the minimum of closure f on the interval [a,b] with Brent's method:
+/// golden section steps are combined with steps to the minimizer of the parabola through
+/// the three best points examined so far (docs/PD.pdf, eq(1.37)). The parabolic step is only
+/// accepted if it falls inside the current bracket and shrinks the step taken two iterations
+/// ago, otherwise we fall back to a golden section step. On smooth functions this converges
+/// superlinearly and typically needs far fewer evaluations of f than golden search.
+///
+/// Unlike golden_search the endpoints a,b are never evaluated.
+///
+/// # Arguments
+///
+/// * `eps` termination criterion: the minimizer is bracketed in an interval of length <= eps
+/// * `max_iter` maximal number of iterations (one evaluation of f each)
+///
+pub fn brent_search<T: Real, F>(f: &F, a: T, b: T, eps: T, max_iter: usize) -> LineSearchResult<T>
+where F: Fn(T) -> T
+{
+    assert!(a<b && eps>T::zero());
+
+    let half: T = real(0.5);
+    let two: T = real(2.0);
+    let c_gold: T = real((3f64-5f64.sqrt())/2f64);
+    let tol1 = eps/real(4.0);
+    let tol2 = two*tol1;
+
+    let mut a = a;
+    let mut b = b;
+    // x: best point so far, w: second best, v: previous value of w
+    let mut x = a+c_gold*(b-a);
+    let mut w = x;
+    let mut v = x;
+    let mut fx = f(x);
+    let mut fw = fx;
+    let mut fv = fx;
+    let mut evaluations = 1;
+    // d: current step, e: step before the last one
+    let mut d = T::zero();
+    let mut e = T::zero();
+
+    let mut iter = 0;
+    while iter < max_iter {
+
+        let xm = half*(a+b);
+        if (x-xm).abs() <= tol2-half*(b-a) {
+            return LineSearchResult{ t: x, f_t: fx, evaluations, converged: true };
+        }
+        let mut golden_step = true;
+        if e.abs() > tol1 {
+
+            // parabola through (x,fx), (w,fw), (v,fv)
+            let r = (x-w)*(fx-fv);
+            let mut q = (x-v)*(fx-fw);
+            let mut p = (x-v)*q-(x-w)*r;
+            q = two*(q-r);
+            if q > T::zero() { p = -p; }
+            q = q.abs();
+            let e_old = e;
+            e = d;
+            if p.abs() < (half*q*e_old).abs() && p > q*(a-x) && p < q*(b-x) {
+
+                // parabolic step
+                d = p/q;
+                let u = x+d;
+                if u-a < tol2 || b-u < tol2 { d = tol1.copysign(xm-x); }
+                golden_step = false;
+            }
+        }
+        if golden_step {
+
+            e = if x >= xm { a-x } else { b-x };
+            d = c_gold*e;
+        }
+        let u = if d.abs() >= tol1 { x+d } else { x+tol1.copysign(d) };
+        let fu = f(u);
+        evaluations += 1;
+
+        if fu <= fx {
+
+            if u >= x { a = x; } else { b = x; }
+            v = w; fv = fw;
+            w = x; fw = fx;
+            x = u; fx = fu;
+        } else {
+
+            if u < x { a = u; } else { b = u; }
+            if fu <= fw || w == x {
+
+                v = w; fv = fw;
+                w = u; fw = fu;
+            } else if fu <= fv || v == x || v == w {
+
+                v = u; fv = fu;
+            }
+        }
+        iter += 1;
+    }
+    LineSearchResult{ t: x, f_t: fx, evaluations, converged: false }
+}
+
+
+
+//------------------ Pluggable line searches ------------------//
+
+/// Result of a one dimensional minimization of a function phi on an interval.
+///
+#[derive(Debug, Clone)]
+pub struct LineSearchResult<T: Real = f64> {
+    /// the minimizer computed
+    pub t: T,
+    /// phi(t)
+    pub f_t: T,
+    /// number of evaluations of phi
+    pub evaluations: usize,
+    /// false if the search was stopped by an iteration limit before the
+    /// termination criterion was satisfied
+    pub converged: bool,
+}
+
+
+/// One dimensional minimizer of a function phi on an interval [a,b].
+/// Used by the Newton step and the initial trust radius computation to search
+/// along a line t -> x+t*d, so we can trade accuracy for function evaluations.
+/// Generic over the scalar type T (f64 by default), the searches below implement it for every T.
+///
+pub trait LineSearch<T: Real = f64> {
+
+    fn id(&self) -> String;
+    /// Attempts to locate the minimum of phi on [a,b].
+    fn minimize(&self, phi: &dyn Fn(T) -> T, a: T, b: T) -> LineSearchResult<T>;
+}
+
+
+/// Line search with golden_search, terminates when the minimizer is
+/// bracketed in an interval of length at most eps.
+///
+#[derive(Debug, Clone)]
+pub struct GoldenSearch {
+    pub eps: f64,
+}
+impl GoldenSearch {
+    pub fn new(eps: f64) -> GoldenSearch { GoldenSearch{ eps } }
+}
+impl<T: Real> LineSearch<T> for GoldenSearch {
+
+    fn id(&self) -> String { format!("GoldenSearch(eps={})",self.eps) }
+    fn minimize(&self, phi: &dyn Fn(T) -> T, a: T, b: T) -> LineSearchResult<T> {
+
+        let evaluations = Cell::new(0usize);
+        let f = |t:T| { evaluations.set(evaluations.get()+1); phi(t) };
+        let (t,f_t) = golden_search(&f,a,b,real(self.eps));
+        LineSearchResult{ t, f_t, evaluations: evaluations.get(), converged: true }
+    }
+}
+
+
+/// Line search with brent_search, terminates when the minimizer is
+/// bracketed in an interval of length at most eps or after max_iter iterations.
+///
+#[derive(Debug, Clone)]
+pub struct BrentSearch {
+    pub eps: f64,
+    pub max_iter: usize,
+}
+impl BrentSearch {
+    pub fn new(eps: f64, max_iter: usize) -> BrentSearch { BrentSearch{ eps, max_iter } }
+}
+impl<T: Real> LineSearch<T> for BrentSearch {
+
+    fn id(&self) -> String { format!("BrentSearch(eps={}, max_iter={})",self.eps,self.max_iter) }
+    fn minimize(&self, phi: &dyn Fn(T) -> T, a: T, b: T) -> LineSearchResult<T> {
+
+        brent_search(&phi,a,b,real(self.eps),self.max_iter)
+    }
+}
+
+
+/// Backtracking line search with the Armijo condition: starting with t=1 the step size t
+/// is multiplied by beta until phi(t) <= phi(0)+alpha*t*slope.
+///
+/// Non finite values of phi (e.g. points outside the domain of the objective function) are
+/// rejected like points violating the Armijo condition.
+///
+/// # Arguments
+///
+/// * `f0` phi(0)
+/// * `slope` derivative phi'(0) < 0
+/// * `alpha` in (0,1/2), fraction of the decrease predicted by the linear approximation
+///   which we accept
+/// * `beta` in (0,1), factor by which t is decreased
+/// * `max_iter` maximal number of backtracking steps
+///
+pub fn backtracking_search<T: Real, F>(
+    phi: &F, f0: T, slope: T, alpha: T, beta: T, max_iter: usize
+) -> LineSearchResult<T>
+where F: Fn(T) -> T
+{
+    assert!(T::zero()<alpha && alpha<real(0.5) && T::zero()<beta && beta<T::one());
+
+    let mut t = T::one();
+    let mut evaluations = 0;
+    while evaluations < max_iter {
+
+        let f_t = phi(t);
+        evaluations += 1;
+        if f_t.is_finite() && f_t <= f0+alpha*t*slope {
+            return LineSearchResult{ t, f_t, evaluations, converged: true };
+        }
+        t *= beta;
+    }
+    LineSearchResult{ t: T::zero(), f_t: f0, evaluations, converged: false }
+}
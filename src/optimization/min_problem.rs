@@ -1,8 +1,10 @@
 use crate::{
     Result, DVec, DMat,
+    sparse::CscMatrix,
+    equation::StructuredMatrix,
     optimization::{
         Region, WholeSpace,
-        global_quadratic_minimizer, golden_search,
+        global_quadratic_minimizer, LineSearch,
         ConstraintSet
     }
 };
@@ -30,10 +32,27 @@ pub trait MinProblem {
     fn hessian(&self, x: &DVec) -> DMat;
     /// domain on which the objective function is minimized
     fn domain(&self) -> &dyn Region;
+    /// i-th component of the gradient, override this if it can be computed
+    /// without computing the full gradient (used in coordinate descent).
+    fn partial_derivative(&self, x: &DVec, i: usize) -> f64 { self.gradient(x)[i] }
+    /// diagonal of the Hessian, override this if it can be computed
+    /// without computing the full Hessian (used in coordinate descent).
+    fn hessian_diagonal(&self, x: &DVec) -> DVec { self.hessian(x).diagonal() }
+    /// Hessian as a sparse matrix (both triangles stored), None if not available.
+    /// Problems with large sparse Hessians should implement this, solvers using it
+    /// (e.g. damped_newton) then never form the dense Hessian. The sparsity pattern
+    /// should be the same at all points x so that symbolic factorizations can be reused.
+    fn sparse_hessian(&self, _x: &DVec) -> Option<CscMatrix> { None }
+
+    /// Hessian in structured form (e.g. diagonal plus low rank), None if not available.
+    /// If available newton_step uses it instead of the dense Hessian, which avoids the
+    /// O(n³) dense factorization.
+    fn structured_hessian(&self, _x: &DVec) -> Option<StructuredMatrix> { None }
 
     /// determined by the behaviour of the objective function f along the line to
-    /// the global minimizer of the quadratic approximation of f
-    fn trust_radius(&self) -> f64 {
+    /// the global minimizer of the quadratic approximation of f, this line is searched
+    /// with the line search `line_search`.
+    fn trust_radius(&self, line_search: &dyn LineSearch) -> f64 {
         let x = self.start_point();
         let g = self.gradient(&x);
         let H = self.hessian(&x);
@@ -48,8 +67,8 @@ pub trait MinProblem {
 
                 // line search in direction of glm
                 let phi = |t: f64| self.objective_fn(&(&x + t * &d));
-                let ls_result = golden_search(&phi, 0f64, 1f64, 0.1);
-                let t_ls = ls_result.0;
+                let ls_result = line_search.minimize(&phi, 0f64, 1f64);
+                let t_ls = ls_result.t;
                 (t_ls * &d.norm()).max(1.0)
             }
         }
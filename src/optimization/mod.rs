@@ -3,7 +3,14 @@ pub use self::{
     newton::*,
     solve::*,
     constraint::*,
-    min_problem::*
+    min_problem::*,
+    damped_newton::*,
+    first_order::*,
+    proximal::*,
+    admm::*,
+    coordinate_descent::*,
+    nonsmooth::*,
+    sqp::*
 };
 use crate::{Result, DVec, DMat};
 
@@ -13,6 +20,13 @@ mod newton;
 mod solve;
 mod constraint;
 mod min_problem;
+mod damped_newton;
+mod first_order;
+mod proximal;
+mod admm;
+mod coordinate_descent;
+mod nonsmooth;
+mod sqp;
 
 
 //--------------------- Domains -------------------//
@@ -84,59 +98,3 @@ impl Region for AllPositive {
     fn dim(&self) -> usize { self.dim }
     fn contains(&self,x: &DVec) -> bool { x.min()>0f64 }
 }
-
-
-
-
-/// Data for problem    ? = argmin_{x in domain} objective_fn(x),
-/// where domain is a convex region G.
-///
-/// Clearly we cannot expect to solve this problem on all convex regions,
-/// even if the objective function is convex.
-/// Typically this will only work if the location of the global minimum
-/// is in G and the objective function f increases as we approach the
-/// boundary of G.
-/// This condition os satisfied by the barrier function on the feasible set
-/// of a convex minimization problem and that is the principal application.
-/// It may also work in some other cases, but there are no guarantees.
-///
-pub trait MinProblem {
-
-    fn id(&self) -> String;
-    fn dim(&self) -> usize;
-    fn start_point(&self) -> DVec;
-    fn objective_fn(&self,x:&DVec) -> f64;
-    fn gradient(&self,x:&DVec) -> DVec;
-    fn hessian(&self,x:&DVec) -> DMat;
-    /// domain on which the objective function is minimized
-    fn domain(&self) -> &dyn Region;
-
-    /// determined by the behaviour of the objective function f along the line to
-    /// the global minimizer of the quadratic approximation of f
-    fn trust_radius(&self) -> f64 {
-
-        let x = self.start_point();
-        let g = self.gradient(&x);
-        let H = self.hessian(&x);
-        // small lambda puts glm far out
-        let lambda = 0.001f64.min(self.gradient(&x).norm() / 10f64);
-        match global_quadratic_minimizer(&x,&g,&H,lambda) {
-
-            Err(e) => 1.0,
-            Ok(glm) => {
-                let D = self.domain();
-                let glm_G = D.retract(&x,&glm);
-                let d = &glm_G-&x;
-
-                // line search in direction of glm
-                let phi = |t:f64| self.objective_fn(&(&x+t*&d));
-                let ls_result = golden_search(&phi,0f64,1f64,0.1);
-                let t_ls = ls_result.0;
-                (t_ls*&d.norm()).max(1.0)
-            }
-        }
-    }
-}
-
-
-
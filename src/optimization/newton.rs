@@ -1,10 +1,13 @@
 use std::fmt;
 use crate::{
     error::ConvOptError, error::ErrKind,
-    equation::cholesky_solve_regularized,
+    equation::{
+        auto_solve, modified_cholesky_solve,
+        StructuredMatrix, LinearSolver, RegularizedSolver
+    },
     Result, DVec, DMat, FUN_nD_TO_1D,
     logging::Logger,
-    optimization::{MinProblem, golden_search}
+    optimization::{MinProblem, LineSearch}
 };
 
 use super::Region;
@@ -12,8 +15,8 @@ use std::cmp::{min, max};
 
 
 /// Solve the regularized Newton equation $(H+l*I)p=-g$ by Cholesky factorization of
-/// $H+l*I$. The matrix $H+l*I$ needs to be positive definite, i.e. if $H$ is singular
-/// we must have $l>0$.
+/// $H+l*I$. If $H+l*I$ is not (numerically) positive definite the modified Cholesky
+/// factorization is used instead, see newton_direction.
 ///
 /// # Arguments
 ///
@@ -23,10 +26,41 @@ use std::cmp::{min, max};
 ///
 pub fn solve_newton_equation(g: &DVec, H: &DMat, l: f64) -> Result<DVec> {
 
-    cholesky_solve_regularized(H,&(-g),l)
+    Ok(newton_direction(g,H,l)?.0)
 }
 
 
+/// Solve the regularized Newton equation $(H+l*I)p=-g$ and return p together with the
+/// additional diagonal shift which was needed to factor $H+l*I$ (in equilibrated coordinates),
+/// the estimated condition number of the (equilibrated) matrix $H+l*I$ and the name of the
+/// method which produced p.
+///
+/// The method is chosen by equation::auto_solve: Cholesky factorization if $H+l*I$ is positive
+/// definite and well conditioned, QR factorization if it is positive definite but ill
+/// conditioned. If the Cholesky factorization fails ($H+l*I$ may be indefinite) or the
+/// solution is not finite the modified Cholesky factorization picks the smallest shift E such
+/// that $H+l*I+E$ can be factored stably, so near-singular or indefinite Hessians do not abort
+/// the solver. The shift is zero in the first two cases, the condition estimate is infinite in
+/// the last case.
+///
+/// Returns (p, shift, condition estimate, "Cholesky", "QR" or "modified Cholesky").
+///
+/// # Arguments
+///
+/// * `H`: symmetric matrix
+/// * `l`: nonnegative scalar (regularization parameter)
+/// * `g`: vector of same dimension as H
+///
+pub fn newton_direction(g: &DVec, H: &DMat, l: f64) -> Result<(DVec,f64,f64,&'static str)> {
+
+    let finite = |p: &DVec| p.iter().all(|p_i| p_i.is_finite());
+    let b = -g;
+    if let Ok((p,method,cond)) = auto_solve(H,&b,l) {
+        if finite(&p) { return Ok((p,0f64,cond,method)); }
+    }
+    let (p,shift) = modified_cholesky_solve(H,&b,l)?;
+    Ok((p,shift,f64::INFINITY,"modified Cholesky"))
+}
 
 
 
@@ -53,6 +87,19 @@ pub struct NewtonStep {
     pub new_trust_radius: f64,
     /// step size to line search point
     pub r_ls: f64,
+    /// number of function evaluations in the line search
+    pub ls_evaluations: usize,
+    /// diagonal shift added by the modified Cholesky factorization to the regularized
+    /// Hessian, zero if the Hessian could be factored as is
+    pub cholesky_shift: f64,
+    /// the method which solved the Newton equation
+    pub linear_solver: String,
+    /// numerical rank of the Hessian if the linear solver determined it (SVD)
+    pub hessian_rank: Option<usize>,
+    /// estimated 1-norm condition number of the (equilibrated) regularized Hessian (the exact
+    /// 2-norm condition number if the equation was solved with a RegularizedSolver),
+    /// NaN if the Newton equation was solved with a structured Hessian or a given LinearSolver
+    pub condition_estimate: f64,
     /// step size to cauchy point
     pub r_cp: f64,
     /// step size to dog leg point
@@ -79,15 +126,20 @@ pub struct NewtonStep {
 impl fmt::Display for NewtonStep {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(format!(
-            "moving to: {}\nold_trust_radius: {1:.5}, new_trust_radius: {2:.5},\n\
+            "moving to: {0}\nold_trust_radius: {1:.5}, new_trust_radius: {2:.5},\n\
             r_ls: {3:.4}, r_cp: {4:.4}, r_dlp: {5:.4}, r_glm: {6:.4},\n\
-            f(x_next): {7:.6},\n\
-            ||gradient(f,x_next)||: {8:.4}\n\
+            line search evaluations: {7}, linear solver: {8}, rank(H): {9}, \
+            cholesky shift: {10:.3e}, cond(H): {11:.3e},\n\
+            f(x_next): {12:.6},\n\
+            ||gradient(f,x_next)||: {13:.4}\n\
             function value decrease (% current iterate):\
-            ls: {9:.6}, cp: {10:.6}, dlp: {11:.6}, glm: {12:.6}\n\
-            next point: {13:.4}",
+            ls: {14:.6}, cp: {15:.6}, dlp: {16:.6}, glm: {17:.6}\n\
+            next point: {18:.4}",
             self.next_point_ID, self.old_trust_radius, self.new_trust_radius,
-            self.r_ls, self.r_cp, self.r_dlp,self.r_glm,
+            self.r_ls, self.r_cp, self.r_dlp, self.r_glm,
+            self.ls_evaluations, self.linear_solver,
+            self.hessian_rank.map_or(String::from("-"),|r| r.to_string()),
+            self.cholesky_shift, self.condition_estimate,
             self.objF_next_point, self.norm_gradient,
             self.ls_decrease, self.cp_decrease, self.dlp_decrease, self.glm_decrease,
             self.next_point
@@ -99,11 +151,11 @@ impl fmt::Display for NewtonStep {
 /// Cauchy point computed from current iterate x, gradient g and Hessian H of objective
 /// function f at x.
 ///
-fn cauchy_point(x:&DVec, g: &DVec, H: &DMat, r: f64) -> DVec {
+fn cauchy_point(x:&DVec, g: &DVec, H: &StructuredMatrix, r: f64) -> DVec {
 
     let norm_g_squared = g.norm_squared();
     let norm_g = norm_g_squared.sqrt();
-    let Hg: DVec = H * g;
+    let Hg: DVec = H.mul_vec(g);
     let q = g.dot(&Hg);
     let t = (r / norm_g).min(norm_g_squared / q);
 
@@ -187,18 +239,68 @@ fn next_trust_radius(r:f64, fx:f64, tp2:f64, points: &Vec<Point>) -> f64 {
 /// * `g`: gradient at current iterate
 /// * `H`: Hessian at current iterate
 /// * `G`: region to which all points are confined
-///       (e.g.: domain of definition of objective function or feasible set).
+///   (e.g.: domain of definition of objective function or feasible set).
 /// * `lambda`: regularization parameter in Newton equation.
+/// * `line_search`: one dimensional minimizer used to search in the direction of the
+///   global minimizer of the quadratic approximation.
+///
+/// * `linear_solver`: solver for the Newton equation, if None the method is chosen by
+///   newton_direction (or the structured Hessian is used).
+///
+/// If `min_prob` provides a structured Hessian and no linear solver is given the Newton
+/// equation is solved with StructuredMatrix::solve_regularized, falling back to
+/// newton_direction on failure. Both apply `lambda` to the Ruiz equilibrated Hessian, i.e.
+/// solve $(H+\lambda D^{-2})p=-g$ with the same scaling D, so lambda means the same
+/// regularization on the structured and the dense path.
 ///
 pub fn newton_step(
-    x: &DVec, min_prob: &dyn MinProblem, r:f64, lambda:f64
+    x: &DVec, min_prob: &dyn MinProblem, r:f64, lambda:f64, line_search: &dyn LineSearch,
+    linear_solver: Option<&dyn LinearSolver>
+) -> Result<NewtonStep> {
+
+    newton_step_with_regularized_solver(x,min_prob,r,lambda,line_search,linear_solver,None)
+}
+
+
+/// Newton step as newton_step, but if no linear solver is given and the Newton equation is
+/// solved with the dense Hessian, the given RegularizedSolver of the Hessian at x is used
+/// instead of newton_direction. This costs O(n²) instead of a factorization, so it pays off
+/// when several steps with different lambda are computed at the same iterate. If
+/// $H+\lambda I$ is not positive definite the modified Cholesky factorization is used
+/// as in newton_direction.
+///
+/// # Arguments
+///
+/// * `regularized_solver`: RegularizedSolver::new(H) for the Hessian H at x.
+///
+pub fn newton_step_with_regularized_solver(
+    x: &DVec, min_prob: &dyn MinProblem, r:f64, lambda:f64, line_search: &dyn LineSearch,
+    linear_solver: Option<&dyn LinearSolver>, regularized_solver: Option<&RegularizedSolver>
 ) -> Result<NewtonStep> {
 
     let G = min_prob.domain();
     assert!(G.contains(x),"iterate x not in region G = {}",G.id());
 
     let g = min_prob.gradient(&x);
-    let H = min_prob.hessian(&x);
+    let H: StructuredMatrix = min_prob.structured_hessian(&x).
+        unwrap_or_else(|| StructuredMatrix::Dense(min_prob.hessian(&x)));
+    assert!(regularized_solver.is_none_or(|s| s.dim()==H.dim()));
+
+    // Newton direction from the dense Hessian M
+    let dense_direction = |M: &DMat| -> Result<(DVec,f64,f64,String,Option<usize>)> {
+        let (p,shift,cond,method) = match regularized_solver {
+            Some(solver) => match solver.solve(&(-&g),lambda) {
+                Ok(p) if p.iter().all(|p_i| p_i.is_finite()) =>
+                    (p,0f64,solver.condition_number(lambda),"RegularizedSolver"),
+                _ => {
+                    let (p,shift) = modified_cholesky_solve(M,&(-&g),lambda)?;
+                    (p,shift,f64::INFINITY,"modified Cholesky")
+                }
+            },
+            None => newton_direction(&g,M,lambda)?
+        };
+        Ok((p,shift,cond,String::from(method),None))
+    };
 
     let mut next_point_id: &str;
     let cp= cauchy_point(x,&g,&H,r);
@@ -206,7 +308,24 @@ pub fn newton_step(
     let r_cp = (x-&cp_G).norm();
 
     // global minimizer glm of quadratic approximation:
-    let glm = global_quadratic_minimizer(x,&g,&H,lambda)?;
+    let (newton_dir,cholesky_shift,condition_estimate,linear_solver,hessian_rank) =
+        match (linear_solver,&H) {
+            (Some(solver),StructuredMatrix::Dense(M)) => {
+                let sol = solver.solve(M,&(-&g),lambda)?;
+                (sol.x,0f64,f64::NAN,sol.solver,sol.rank)
+            }
+            (Some(solver),_) => {
+                let sol = solver.solve(&H.to_dense(),&(-&g),lambda)?;
+                (sol.x,0f64,f64::NAN,sol.solver,sol.rank)
+            }
+            (None,StructuredMatrix::Dense(M)) => dense_direction(M)?,
+            (None,_) => match H.solve_regularized(&(-&g),lambda) {
+                Ok(p) if p.iter().all(|p_i| p_i.is_finite()) =>
+                    (p,0f64,f64::NAN,String::from("structured"),None),
+                _ => dense_direction(&H.to_dense())?
+            }
+        };
+    let glm: DVec = x+newton_dir;
     let mut glm_G: DVec = G.retract(x,&glm);
     let mut r_glm = (x-&glm_G).norm();
 
@@ -214,11 +333,11 @@ pub fn newton_step(
     let p = &glm_G-x;   // note: shorter than newton step because of retraction
     let f = |z: &DVec| min_prob.objective_fn(z);
     let phi = |t:f64| f(&(x+t*&p));
-    let ls_result = golden_search(&phi,0f64,2f64,0.1f64);
-    let t_ls = ls_result.0;
+    let ls_result = line_search.minimize(&phi,0f64,2f64);
+    let t_ls = ls_result.t;
     let ls: DVec = x+t_ls*&p;            // minimizer of f in direction of glm
     let r_ls: f64 = t_ls*&p.norm();           // ||ls-x||
-    let f_ls = ls_result.1;
+    let f_ls = ls_result.f_t;
 
     // dog-leg point, useful ony if b is outside the trust radius
     let d = &glm-&cp;    // direction from cp to glm
@@ -267,7 +386,7 @@ pub fn newton_step(
     // new trust radius
     // tp2: quadratic approximation of f centered at next point
     let h = &next_point;
-    let tp2 = fx + (&g.dot(h) + 0.5f64*(&H * h).dot(h));
+    let tp2 = fx + (&g.dot(h) + 0.5f64*H.mul_vec(h).dot(h));
 
     let points = vec![(r_ls,f_ls),(r_glm,f_glm),(r_cp,f_cp),(r_dlp,f_dlp)];
     let new_trust_radius = next_trust_radius(r,fx,tp2,&points);
@@ -281,6 +400,11 @@ pub fn newton_step(
         old_trust_radius: r,
         new_trust_radius,
         r_ls,
+        ls_evaluations: ls_result.evaluations,
+        cholesky_shift,
+        linear_solver,
+        hessian_rank,
+        condition_estimate,
         r_cp,
         r_dlp,
         r_glm,
@@ -0,0 +1,264 @@
+use std::fmt;
+use crate::{
+    DVec, DMat,
+    optimization::project_simplex
+};
+
+
+
+/// Data for problem    ? = argmin_x f(x)  on R^n,
+/// where f is convex but not necessarily differentiable, given by its values and
+/// one subgradient at each point.
+///
+pub trait NonsmoothProblem {
+
+    fn id(&self) -> String;
+    fn dim(&self) -> usize;
+    fn start_point(&self) -> DVec;
+    fn objective_fn(&self, x: &DVec) -> f64;
+    /// some g with f(y) >= f(x)+g'(y-x) for all y
+    fn subgradient(&self, x: &DVec) -> DVec;
+}
+
+
+
+//------------------ Subgradient method ------------------//
+
+/// Step size rule of the subgradient method x_{k+1} = x_k - t_k*g_k.
+///
+#[derive(Clone,Debug)]
+pub enum SubgradientStep {
+    /// Polyak step t_k = (f(x_k)-f_opt)/||g_k||² with the optimal value f_opt
+    /// (or a lower estimate of it).
+    Polyak(f64),
+    /// t_k = a/(sqrt(k+1)*||g_k||), i.e. steps of length a/sqrt(k+1).
+    Diminishing(f64),
+    /// t_k = a/(k+1), square summable but not summable.
+    SquareSummable(f64),
+}
+
+
+/// Result of the subgradient method: the best point found.
+///
+#[derive(Debug)]
+pub struct SubgradientResult {
+    pub x_best: DVec,
+    pub f_best: f64,
+    pub iterations: usize,
+    /// true if a zero subgradient was found or, for the Polyak step, f(x_best)-f_opt <= eps,
+    /// false if the method stopped after max_iter iterations
+    pub converged: bool,
+}
+
+impl fmt::Display for SubgradientResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "converged: {0}, iterations: {1}, f(x_best): {2:.8}\nx_best: {3:.6}",
+            self.converged, self.iterations, self.f_best, self.x_best
+        ).as_str())
+    }
+}
+
+
+/// Minimizes the objective function of `prob` with the subgradient method
+/// x_{k+1} = x_k - t_k*g_k, g_k a subgradient at x_k.
+///
+/// This is not a descent method, the best point found is returned. There is no computable
+/// optimality certificate, so the iteration runs for max_iter iterations unless a zero
+/// subgradient is found or, for the Polyak step, f(x_best)-f_opt <= eps. Only in these cases
+/// the result is flagged as `converged`.
+///
+pub fn subgradient_method(
+    prob: &dyn NonsmoothProblem, step: &SubgradientStep, eps: f64, max_iter: usize
+) -> SubgradientResult {
+
+    let mut x = prob.start_point();
+    let mut x_best = x.clone();
+    let mut f_best = prob.objective_fn(&x);
+
+    let mut converged = false;
+    let mut iter = 0;
+    while iter < max_iter {
+
+        let fx = prob.objective_fn(&x);
+        if fx < f_best { f_best = fx; x_best = x.clone(); }
+        if let SubgradientStep::Polyak(f_opt) = step {
+            if f_best-f_opt <= eps { converged = true; break; }
+        }
+        let g = prob.subgradient(&x);
+        let norm_g_sq = g.norm_squared();
+        if norm_g_sq == 0f64 { converged = true; break; }    // x is optimal
+
+        let k = iter as f64;
+        let t = match step {
+            SubgradientStep::Polyak(f_opt) => (fx-f_opt).max(0f64)/norm_g_sq,
+            SubgradientStep::Diminishing(a) => a/((k+1f64).sqrt()*norm_g_sq.sqrt()),
+            SubgradientStep::SquareSummable(a) => a/(k+1f64)
+        };
+        x -= t*&g;
+        iter += 1;
+    }
+    let fx = prob.objective_fn(&x);
+    if fx < f_best { f_best = fx; x_best = x; }
+    SubgradientResult{ x_best, f_best, iterations: iter, converged }
+}
+
+
+
+//------------------ Proximal bundle method ------------------//
+
+/// Result of the proximal bundle method.
+///
+/// Convexity of f and the aggregate linearization yield the certificate
+///     f(x) >= objF_x - aggregate_error + aggregate_subgradient'(x-x_center)
+/// for all x, in particular f(x) >= lower_bound(r) for all x with ||x-x_center|| <= r.
+///
+#[derive(Debug)]
+pub struct BundleResult {
+    /// stability center (best point found)
+    pub x: DVec,
+    pub objF_x: f64,
+    /// aggregate_error + ||aggregate_subgradient||²/mu = f(x) - m(y), the decrease the cutting
+    /// plane model m (without the proximal term) predicts at the candidate
+    /// y = x - aggregate_subgradient/mu, the stopping criterion
+    pub predicted_decrease: f64,
+    /// linearization error of the aggregate cut at x
+    pub aggregate_error: f64,
+    pub aggregate_subgradient: DVec,
+    pub iterations: usize,
+    /// number of iterations in which the stability center moved
+    pub serious_steps: usize,
+    /// true if predicted_decrease <= eps, false if the method stopped after max_iter iterations
+    pub converged: bool,
+}
+impl BundleResult {
+
+    /// lower bound for f on the ball of radius r around x.
+    pub fn lower_bound(&self, r: f64) -> f64 {
+        self.objF_x-self.aggregate_error-r*self.aggregate_subgradient.norm()
+    }
+}
+
+impl fmt::Display for BundleResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "converged: {0}, iterations: {1}, serious steps: {2}, f(x): {3:.8}, \
+            predicted decrease: {4:.4e},\n\
+            aggregate error: {5:.4e}, ||aggregate subgradient||: {6:.4e}\nx: {7:.6}",
+            self.converged, self.iterations, self.serious_steps, self.objF_x,
+            self.predicted_decrease, self.aggregate_error, self.aggregate_subgradient.norm(),
+            self.x
+        ).as_str())
+    }
+}
+
+
+/// Cut f(y) >= f(x_center) - e + g'(y-x_center) of the cutting plane model, stored
+/// relative to the current stability center.
+struct Cut {
+    g: DVec,
+    e: f64,
+}
+
+
+/// Solves the dual of the proximal bundle subproblem
+///     min_{alpha in simplex} sum_j alpha_j*e_j + (1/(2mu))||sum_j alpha_j*g_j||²
+/// by projected gradient iteration. Returns alpha.
+///
+fn bundle_dual(cuts: &[Cut], mu: f64) -> DVec {
+
+    let m = cuts.len();
+    let n = cuts[0].g.len();
+    let G = DMat::from_fn(n,m,|i,j| cuts[j].g[i]);
+    let e = DVec::from_fn(m,|j,_| cuts[j].e);
+    let Q = G.tr_mul(&G)/mu;
+    let L = Q.norm().max(1e-12);     // Lipschitz constant of the gradient Q*alpha+e
+
+    let mut alpha = DVec::repeat(m,0f64);
+    alpha[m-1] = 1f64;
+    let mut k = 0;
+    while k < 500 {
+
+        let alpha_next = project_simplex(&(&alpha-(&Q*&alpha+&e)/L),1f64);
+        let change = (&alpha_next-&alpha).amax();
+        alpha = alpha_next;
+        if change < 1e-12 { break; }
+        k += 1;
+    }
+    alpha
+}
+
+
+/// Minimizes the objective function of `prob` with the proximal bundle method: the
+/// cutting plane model m(y) = max_j f(x_j)+g_j'(y-x_j) built from the subgradients g_j at
+/// all points x_j examined so far is minimized with the proximal term (mu/2)||y-x_center||².
+/// If the decrease f(x_center)-f(y) is at least m_ratio times the predicted decrease
+/// f(x_center)-m(y) the center moves to y (serious step), otherwise only the cut at y is
+/// added to the model (null step).
+///
+/// The iteration stops when the predicted decrease is at most eps (the result is flagged as
+/// `converged`) or after max_iter iterations. When the bundle exceeds
+/// max_bundle_size cuts it is compressed to the aggregate cut and the newest cut.
+///
+/// # Arguments
+///
+/// * `mu`: weight of the proximal term > 0.
+/// * `m_ratio`: in (0,1), fraction of the predicted decrease needed for a serious step.
+/// * `eps`: termination criterion predicted decrease <= eps.
+/// * `max_iter`: maximal number of iterations.
+/// * `max_bundle_size`: maximal number of cuts kept in the model (>= 2).
+///
+pub fn proximal_bundle(
+    prob: &dyn NonsmoothProblem, mu: f64, m_ratio: f64, eps: f64,
+    max_iter: usize, max_bundle_size: usize
+) -> BundleResult {
+
+    assert!(mu > 0f64 && 0f64 < m_ratio && m_ratio < 1f64 && max_bundle_size >= 2);
+
+    let mut x = prob.start_point();
+    let mut fx = prob.objective_fn(&x);
+    let mut cuts = vec![Cut{ g: prob.subgradient(&x), e: 0f64 }];
+    let mut serious_steps = 0;
+
+    let mut iter = 0;
+    loop {
+        let alpha = bundle_dual(&cuts,mu);
+        let g_agg: DVec = cuts.iter().zip(alpha.iter()).
+            fold(DVec::repeat(x.len(),0f64),|s,(cut,a)| s+*a*&cut.g);
+        let e_agg: f64 = cuts.iter().zip(alpha.iter()).map(|(cut,a)| a*cut.e).sum();
+        let predicted_decrease = e_agg+g_agg.norm_squared()/mu;
+
+        if predicted_decrease <= eps || iter == max_iter {
+            return BundleResult{
+                x, objF_x: fx, predicted_decrease, aggregate_error: e_agg,
+                aggregate_subgradient: g_agg, iterations: iter, serious_steps,
+                converged: predicted_decrease <= eps
+            };
+        }
+        let y = &x-(1f64/mu)*&g_agg;
+        let fy = prob.objective_fn(&y);
+        let gy = prob.subgradient(&y);
+
+        if cuts.len() >= max_bundle_size {
+            cuts = vec![Cut{ g: g_agg, e: e_agg }];
+        }
+        if fx-fy >= m_ratio*predicted_decrease {
+
+            // serious step, recompute the linearization errors at the new center
+            let d = &y-&x;
+            for cut in cuts.iter_mut() {
+                cut.e = (cut.e+fy-fx-cut.g.dot(&d)).max(0f64);
+            }
+            cuts.push(Cut{ g: gy, e: 0f64 });
+            x = y;
+            fx = fy;
+            serious_steps += 1;
+        } else {
+
+            // null step
+            let e = (fx-fy-gy.dot(&(&x-&y))).max(0f64);
+            cuts.push(Cut{ g: gy, e });
+        }
+        iter += 1;
+    }
+}
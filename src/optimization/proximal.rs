@@ -0,0 +1,359 @@
+use crate::{
+    error::ConvOptError, error::ErrKind,
+    Result, DVec,
+    optimization::{MinProblem, FirstOrderResult}
+};
+
+
+
+//------------------ Proximal operators ------------------//
+
+/// Closed, convex, possibly nonsmooth function h: R^n -> (-oo,+oo] given by its
+/// proximal operator
+///     prox_{t*h}(x) = argmin_u h(u) + ||u-x||²/(2t),   t > 0.
+/// Indicator functions of convex sets are allowed (value +oo outside the set), their
+/// proximal operator is the projection onto the set.
+///
+pub trait ProxOperator {
+
+    fn id(&self) -> String;
+    /// h(x), f64::INFINITY if x is not in the domain of h.
+    fn value(&self, x: &DVec) -> f64;
+    /// prox_{t*h}(x)
+    fn prox(&self, x: &DVec, t: f64) -> DVec;
+}
+
+
+/// Componentwise soft thresholding sign(x_i)*max(|x_i|-s,0).
+///
+pub fn soft_threshold(x: &DVec, s: f64) -> DVec {
+
+    x.map(|u| u.signum()*(u.abs()-s).max(0f64))
+}
+
+
+/// Euclidean projection of v onto the simplex {x >= 0, sum_i x_i = r}, r > 0.
+/// Sorts v, complexity O(n*log(n)).
+///
+pub fn project_simplex(v: &DVec, r: f64) -> DVec {
+
+    assert!(r > 0f64);
+    let n = v.len();
+    let mut u: Vec<f64> = v.iter().cloned().collect();
+    u.sort_by(|a:&f64,b:&f64| b.partial_cmp(a).unwrap());
+
+    // theta = (sum_{i<=rho}u_i-r)/rho with rho the largest j with u_j > (sum_{i<=j}u_i-r)/j
+    let mut s = 0f64;
+    let mut theta = 0f64;
+    let mut j = 0;
+    while j < n {
+
+        s += u[j];
+        let q = (s-r)/((j+1) as f64);
+        if u[j] > q { theta = q; }
+        j += 1;
+    }
+    v.map(|x| (x-theta).max(0f64))
+}
+
+
+/// h(x) = lambda*||x||_1
+#[derive(Clone,Debug)]
+pub struct L1Norm {
+    pub lambda: f64,
+}
+impl L1Norm {
+    pub fn new(lambda: f64) -> L1Norm { L1Norm{ lambda } }
+}
+impl ProxOperator for L1Norm {
+
+    fn id(&self) -> String { format!("L1Norm(lambda={})",self.lambda) }
+    fn value(&self, x: &DVec) -> f64 { self.lambda*x.lp_norm(1) }
+    fn prox(&self, x: &DVec, t: f64) -> DVec { soft_threshold(x,t*self.lambda) }
+}
+
+
+/// Group lasso penalty h(x) = lambda*sum_k ||x_{G_k}||_2 for disjoint groups G_k of
+/// coordinates. Coordinates not in any group are not penalized.
+#[derive(Clone,Debug)]
+pub struct GroupL1Norm {
+    pub lambda: f64,
+    pub groups: Vec<Vec<usize>>,
+}
+impl GroupL1Norm {
+    pub fn new(lambda: f64, groups: Vec<Vec<usize>>) -> GroupL1Norm { GroupL1Norm{ lambda, groups } }
+}
+impl ProxOperator for GroupL1Norm {
+
+    fn id(&self) -> String { format!("GroupL1Norm(lambda={})",self.lambda) }
+    fn value(&self, x: &DVec) -> f64 {
+        self.groups.iter().
+            map(|grp| grp.iter().map(|&i| x[i]*x[i]).sum::<f64>().sqrt()).
+            sum::<f64>()*self.lambda
+    }
+    fn prox(&self, x: &DVec, t: f64) -> DVec {
+
+        let mut u = x.clone();
+        for grp in self.groups.iter() {
+
+            let norm = grp.iter().map(|&i| x[i]*x[i]).sum::<f64>().sqrt();
+            let q = if norm > 0f64 { (1f64-t*self.lambda/norm).max(0f64) } else { 0f64 };
+            for &i in grp.iter() { u[i] = q*x[i]; }
+        }
+        u
+    }
+}
+
+
+/// Elastic net penalty h(x) = l1*||x||_1 + (l2/2)*||x||_2².
+#[derive(Clone,Debug)]
+pub struct ElasticNet {
+    pub l1: f64,
+    pub l2: f64,
+}
+impl ElasticNet {
+    pub fn new(l1: f64, l2: f64) -> ElasticNet { ElasticNet{ l1, l2 } }
+}
+impl ProxOperator for ElasticNet {
+
+    fn id(&self) -> String { format!("ElasticNet(l1={}, l2={})",self.l1,self.l2) }
+    fn value(&self, x: &DVec) -> f64 { self.l1*x.lp_norm(1)+0.5f64*self.l2*x.norm_squared() }
+    fn prox(&self, x: &DVec, t: f64) -> DVec {
+        soft_threshold(x,t*self.l1)/(1f64+t*self.l2)
+    }
+}
+
+
+/// Indicator function of the box {lower <= x <= upper}.
+#[derive(Clone,Debug)]
+pub struct BoxIndicator {
+    pub lower: DVec,
+    pub upper: DVec,
+}
+impl BoxIndicator {
+    pub fn new(lower: DVec, upper: DVec) -> BoxIndicator {
+        assert!(lower.len()==upper.len());
+        BoxIndicator{ lower, upper }
+    }
+}
+impl ProxOperator for BoxIndicator {
+
+    fn id(&self) -> String { String::from("BoxIndicator") }
+    fn value(&self, x: &DVec) -> f64 {
+        let inside = x.iter().zip(self.lower.iter().zip(self.upper.iter())).
+            all(|(u,(a,b))| a <= u && u <= b);
+        if inside { 0f64 } else { f64::INFINITY }
+    }
+    fn prox(&self, x: &DVec, _t: f64) -> DVec {
+        DVec::from_fn(x.len(),|i,_| x[i].max(self.lower[i]).min(self.upper[i]))
+    }
+}
+
+
+/// Indicator function of the simplex {x >= 0, sum_i x_i = radius}.
+#[derive(Clone,Debug)]
+pub struct SimplexIndicator {
+    pub radius: f64,
+}
+impl SimplexIndicator {
+    pub fn new(radius: f64) -> SimplexIndicator { SimplexIndicator{ radius } }
+}
+impl ProxOperator for SimplexIndicator {
+
+    fn id(&self) -> String { format!("SimplexIndicator(radius={})",self.radius) }
+    fn value(&self, x: &DVec) -> f64 {
+        let tol = 1e-10*self.radius.max(1f64);
+        if x.min() >= 0f64 && (x.sum()-self.radius).abs() <= tol { 0f64 } else { f64::INFINITY }
+    }
+    fn prox(&self, x: &DVec, _t: f64) -> DVec { project_simplex(x,self.radius) }
+}
+
+
+/// Indicator function of the closed first orthant {x >= 0}, the closure of the
+/// region AllPositive.
+#[derive(Clone,Debug)]
+pub struct NonNegativeIndicator {
+    pub dim: usize,
+}
+impl NonNegativeIndicator {
+    pub fn new(dim: usize) -> NonNegativeIndicator { NonNegativeIndicator{ dim } }
+}
+impl ProxOperator for NonNegativeIndicator {
+
+    fn id(&self) -> String { String::from("NonNegativeIndicator") }
+    fn value(&self, x: &DVec) -> f64 { if x.min() >= 0f64 { 0f64 } else { f64::INFINITY } }
+    fn prox(&self, x: &DVec, _t: f64) -> DVec { x.map(|u| u.max(0f64)) }
+}
+
+
+
+//------------------ Composite problems ------------------//
+
+/// Problem    ? = argmin_x f(x)+h(x),
+/// where f is smooth (C2) and h is convex but possibly nonsmooth and given by its
+/// proximal operator. The start point and the domain are those of f.
+///
+pub trait CompositeProblem {
+
+    fn id(&self) -> String;
+    fn smooth_part(&self) -> &dyn MinProblem;
+    fn nonsmooth_part(&self) -> &dyn ProxOperator;
+    fn objective_fn(&self, x: &DVec) -> f64 {
+        self.smooth_part().objective_fn(x)+self.nonsmooth_part().value(x)
+    }
+}
+
+
+/// CompositeProblem f(x)+h(x) assembled from a MinProblem f and a ProxOperator h.
+///
+pub struct Composite<F: MinProblem, H: ProxOperator> {
+    pub smooth: F,
+    pub nonsmooth: H,
+}
+impl<F: MinProblem, H: ProxOperator> Composite<F,H> {
+    pub fn new(smooth: F, nonsmooth: H) -> Composite<F,H> { Composite{ smooth, nonsmooth } }
+}
+impl<F: MinProblem, H: ProxOperator> CompositeProblem for Composite<F,H> {
+
+    fn id(&self) -> String { self.smooth.id()+" + "+self.nonsmooth.id().as_str() }
+    fn smooth_part(&self) -> &dyn MinProblem { &self.smooth }
+    fn nonsmooth_part(&self) -> &dyn ProxOperator { &self.nonsmooth }
+}
+
+
+
+//------------------ Proximal gradient method ------------------//
+
+/// Proximal gradient step x+ = prox_{h/L}(y-g/L) from the point y with gradient g of the
+/// smooth part f, where the Lipschitz estimate L is doubled until
+///     f(x+) <= f(y)+g'(x+-y)+(L/2)||x+-y||².
+/// Points outside the domain of f are rejected. The condition is checked up to rounding errors
+/// in f, otherwise L blows up close to the minimizer. Returns (x+,L) or None if no such L was found.
+///
+fn proximal_step(
+    prob: &dyn CompositeProblem, y: &DVec, f_y: f64, g: &DVec, L: f64
+) -> Option<(DVec,f64)> {
+
+    let f = prob.smooth_part();
+    let h = prob.nonsmooth_part();
+    let rounding = 10f64*f64::EPSILON*f_y.abs();
+    let mut L = L;
+    let mut k = 0;
+    while k < 100 {
+
+        let x = h.prox(&(y-(1f64/L)*g),1f64/L);
+        if f.domain().contains(&x) {
+
+            let d = &x-y;
+            if f.objective_fn(&x) <= f_y+g.dot(&d)+0.5f64*L*d.norm_squared()+rounding {
+                return Some((x,L));
+            }
+        }
+        L *= 2f64;
+        k += 1;
+    }
+    None
+}
+
+
+/// Minimizes the composite objective f(x)+h(x) with the proximal gradient method
+/// x_{k+1} = prox_{h/L}(x_k-g(x_k)/L), L determined by backtracking.
+///
+/// The iteration stops when the norm of the gradient mapping L*(x_k-x_{k+1}) (which reduces
+/// to the gradient for h=0) is less than eps*sqrt(dim). The field norm_gradient of the result
+/// holds the norm of the gradient mapping.
+///
+/// # Arguments
+///
+/// * `eps`: termination criterion ||L*(x_k-x_{k+1})|| < eps*sqrt(dim).
+/// * `max_iter`: maximal number of iterations.
+///
+pub fn proximal_gradient(
+    prob: &dyn CompositeProblem, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    composite_gradient(prob, false, eps, max_iter)
+}
+
+
+/// Accelerated proximal gradient method (FISTA with backtracking) for the composite objective
+/// f(x)+h(x). The momentum is reset whenever the gradient mapping at the extrapolated point
+/// points against the last step (adaptive restart).
+///
+/// # Arguments
+///
+/// * `eps`: termination criterion ||L*(y_k-x_{k+1})|| < eps*sqrt(dim).
+/// * `max_iter`: maximal number of iterations.
+///
+pub fn accelerated_proximal_gradient(
+    prob: &dyn CompositeProblem, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    composite_gradient(prob, true, eps, max_iter)
+}
+
+
+fn composite_gradient(
+    prob: &dyn CompositeProblem, accelerated: bool, eps: f64, max_iter: usize
+) -> Result<FirstOrderResult> {
+
+    let f = prob.smooth_part();
+    let h = prob.nonsmooth_part();
+    let G = f.domain();
+    let rho = eps*(f.dim() as f64).sqrt();
+
+    // the start point may not be in the domain of h
+    let x_0 = f.start_point();
+    let mut x = h.prox(&x_0,1f64);
+    if !G.contains(&x) { x = x_0; }
+    let mut y = x.clone();
+    let mut L = 1f64;
+    let mut theta = 1f64;
+    let mut restarts = 0;
+
+    let mut iter = 0;
+    loop {
+        if iter == max_iter {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit")));
+        }
+        let f_y = f.objective_fn(&y);
+        let g_y = f.gradient(&y);
+        if iter > 0 { L *= 0.9f64; }
+        let (x_next,L_next) = match proximal_step(prob,&y,f_y,&g_y,L) {
+            Some(step) => step,
+            None => return Err(ConvOptError::new(ErrKind::ConvergenceFailure(
+                "no Lipschitz estimate found in proximal gradient step"
+            )))
+        };
+        L = L_next;
+        let gradient_mapping = L*(&y-&x_next);
+        iter += 1;
+
+        if gradient_mapping.norm() < rho {
+            return Ok(FirstOrderResult{
+                objF_x: prob.objective_fn(&x_next), x: x_next,
+                norm_gradient: gradient_mapping.norm(), iterations: iter,
+                restarts, lipschitz_estimate: L
+            });
+        }
+        if accelerated {
+
+            let theta_next = 0.5f64*(1f64+(1f64+4f64*theta*theta).sqrt());
+            let dx = &x_next-&x;
+            let y_next = &x_next+((theta-1f64)/theta_next)*&dx;
+            if gradient_mapping.dot(&dx) > 0f64 || !G.contains(&y_next) {
+
+                theta = 1f64;
+                y = x_next.clone();
+                restarts += 1;
+            } else {
+
+                theta = theta_next;
+                y = y_next;
+            }
+        } else {
+            y = x_next.clone();
+        }
+        x = x_next;
+    }
+}
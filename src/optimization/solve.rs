@@ -1,12 +1,13 @@
 
 use crate::{
-    error::ConvOptError, error::ErrKind,
+    error::ConvOptError, error::ErrKind, error::StallDiagnostics,
     Result, DVec, DMat, FUN_nD_TO_1D,
-    logging::Logger
+    logging::Logger,
+    equation::{LinearSolver, RegularizedSolver}
 };
 
 use super::newton::*;
-use super::{MinProblem,Region};
+use super::{MinProblem,Region,LineSearch};
 
 
 /// The regularization parameter lambda in newton::newton_step
@@ -19,36 +20,198 @@ fn reg_lambda(r: f64, g: &DVec) -> f64 {
 }
 
 
-pub fn solve_min_problem(min_prob: &impl MinProblem, eps:f64, max_iter:usize) -> Result<DVec> {
+/// Number of consecutive Newton steps without progress after which the
+/// next recovery measure is tried.
+const STALL_STEPS: usize = 2;
 
+
+/// A Newton step makes progress if it decreases the objective function by more than
+/// rounding errors.
+///
+fn makes_progress(fx: f64, step: &NewtonStep) -> bool {
+
+    fx-step.objF_next_point > 1e-15*(1f64+fx.abs())
+}
+
+
+/// Steepest descent step from x: line search along the negative gradient g on the
+/// segment of length r pulled back into the domain. Returns the new point or None if
+/// there is no decrease in the objective function.
+///
+fn steepest_descent_step(
+    x: &DVec, g: &DVec, min_prob: &dyn MinProblem, r: f64, line_search: &dyn LineSearch
+) -> Option<DVec> {
+
+    let norm_g = g.norm();
+    if norm_g == 0f64 { return None; }
+    let G = min_prob.domain();
+    let d = G.retract(x,&(x-(r/norm_g)*g))-x;
+    let phi = |t:f64| min_prob.objective_fn(&(x+t*&d));
+    let ls_result = line_search.minimize(&phi,0f64,1f64);
+    let fx = min_prob.objective_fn(x);
+    if fx-ls_result.f_t > 1e-15*(1f64+fx.abs()) { Some(x+ls_result.t*&d) } else { None }
+}
+
+
+/// Options for solve_min_problem_with_options.
+///
+pub struct NewtonOptions {
+    /// termination criterion ||grad(f)(x)|| < eps*sqrt(dim)
+    pub eps: f64,
+    /// maximal number of Newton steps
+    pub max_iter: usize,
+    /// solver for the Newton equations, e.g. FallbackSolver, if None newton_step picks
+    /// the method (see newton::newton_direction)
+    pub linear_solver: Option<Box<dyn LinearSolver>>,
+}
+
+impl Default for NewtonOptions {
+    fn default() -> NewtonOptions {
+        NewtonOptions{ eps: 1e-8, max_iter: 100, linear_solver: None }
+    }
+}
+
+
+/// Minimizes the objective function of `min_prob` with trust region Newton steps,
+/// see solve_min_problem_with_options.
+///
+/// # Arguments
+///
+/// * `line_search`: one dimensional minimizer used in the Newton steps and
+///   the computation of the initial trust radius.
+/// * `eps`: termination criterion ||grad(f)(x)|| < eps*sqrt(dim)
+/// * `max_iter`: maximal number of Newton steps.
+///
+pub fn solve_min_problem(
+    min_prob: &impl MinProblem, line_search: &dyn LineSearch, eps:f64, max_iter:usize
+) -> Result<DVec> {
+
+    let options = NewtonOptions{ eps, max_iter, linear_solver: None };
+    solve_min_problem_with_options(min_prob, line_search, &options)
+}
+
+
+/// Minimizes the objective function of `min_prob` with trust region Newton steps.
+///
+/// If STALL_STEPS consecutive Newton steps make no progress the solver tries to recover,
+/// escalating the measure each time this happens again without progress in between:
+/// first the trust radius is shrunk, then the regularization parameter lambda is increased,
+/// finally a steepest descent step is taken. If this does not decrease the objective
+/// function either the solver gives up with ErrKind::Stalled carrying the diagnostics.
+///
+/// The Newton equations are solved with `options.linear_solver`, the solver used in each
+/// step is reported in the log. If no linear solver is given and a step makes no progress,
+/// the following steps are computed at the same iterate with other trust radii and lambdas,
+/// so the (dense) Hessian there is decomposed once with RegularizedSolver and all these
+/// steps solve their Newton equations in O(n²) (see newton_step_with_regularized_solver).
+///
+/// # Arguments
+///
+/// * `line_search`: one dimensional minimizer used in the Newton steps and
+///   the computation of the initial trust radius.
+/// * `options`: tolerance, maximal number of Newton steps and linear solver.
+///
+pub fn solve_min_problem_with_options(
+    min_prob: &impl MinProblem, line_search: &dyn LineSearch, options: &NewtonOptions
+) -> Result<DVec> {
+
+    let eps = options.eps;
+    let max_iter = options.max_iter;
+    let linear_solver = options.linear_solver.as_deref();
     let mut iter = 0;
     let rho = eps*(min_prob.dim() as f64).sqrt();
     let mut logger = Logger::new(format!("results/{}.log",min_prob.id()).as_str());
 
     let mut x = min_prob.start_point();
-    let mut r= min_prob.trust_radius();
+    let mut fx = min_prob.objective_fn(&x);
+    let mut r= min_prob.trust_radius(line_search);
     let mut grad = min_prob.gradient(&x);
     let mut lambda = reg_lambda(r, &grad);
 
+    // stagnation detection and recovery
+    let mut stalled_steps = 0;          // consecutive Newton steps without progress
+    let mut recovery_attempts: Vec<&'static str> = Vec::new();
+    let mut lambda_factor = 1f64;
+    // decomposition of the Hessian at x, built when x is kept for another step
+    let mut hessian_solver: Option<RegularizedSolver> = None;
+
     logger.write(format!("\n\nOptimization starts at point {}",&x).as_str());
     logger.write(format!(
-        "f(x): {0:.2}, ||grad(f)(x)||: {1:.3}",min_prob.objective_fn(&x),grad.norm()
+        "f(x): {0:.2}, ||grad(f)(x)||: {1:.3}",fx,grad.norm()
     ).as_str());
 
-    while(iter<=max_iter && grad.norm()>=rho){
+    while iter<max_iter && grad.norm()>=rho {
 
-        let step = newton_step(&x, min_prob, r, lambda)?;
+        let step = newton_step_with_regularized_solver(
+            &x, min_prob, r, lambda, line_search, linear_solver, hessian_solver.as_ref()
+        )?;
         logger.write(format!("\n\nIteration: {}\nstep: {}",iter,&step).as_str());
-        x = step.next_point;
         r = step.new_trust_radius;
+        let mut moved = false;
+
+        if makes_progress(fx,&step) {
+
+            moved = true;
+            x = step.next_point;
+            fx = step.objF_next_point;
+            stalled_steps = 0;
+            recovery_attempts.clear();
+            lambda_factor = 1f64;
+        } else {
+
+            stalled_steps += 1;
+        }
+        if stalled_steps >= STALL_STEPS {
+
+            stalled_steps = 0;
+            match recovery_attempts.len() {
+                0 => {
+                    r /= 4f64;
+                    recovery_attempts.push("shrink trust radius");
+                },
+                1 => {
+                    lambda_factor *= 100f64;
+                    recovery_attempts.push("increase lambda");
+                },
+                _ => {
+                    recovery_attempts.push("steepest descent step");
+                    match steepest_descent_step(&x, &grad, min_prob, r, line_search) {
+                        Some(z) => {
+                            moved = true;
+                            x = z;
+                            fx = min_prob.objective_fn(&x);
+                            recovery_attempts.clear();
+                            lambda_factor = 1f64;
+                        },
+                        None => {
+                            logger.write("\n\nSolver stalled, giving up.");
+                            return Err(ConvOptError::new(ErrKind::Stalled(StallDiagnostics{
+                                iteration: iter, point: x, objective_value: fx,
+                                gradient_norm: grad.norm(), trust_radius: r, lambda,
+                                recovery_attempts
+                            })));
+                        }
+                    }
+                }
+            }
+            logger.write(format!(
+                "\n\nNo progress in {} steps, recovery: {}",
+                STALL_STEPS, recovery_attempts.last().unwrap_or(&"steepest descent step")
+            ).as_str());
+        }
+        if moved {
+            hessian_solver = None;
+        } else if hessian_solver.is_none() && linear_solver.is_none() &&
+            min_prob.structured_hessian(&x).is_none() {
+            hessian_solver = Some(RegularizedSolver::new(&min_prob.hessian(&x)));
+        }
         grad = min_prob.gradient(&x);
-        lambda = reg_lambda(r, &grad);
+        lambda = lambda_factor*reg_lambda(r, &grad);
         iter +=1;
     }
-    if iter==max_iter && grad.norm()>=rho {
+    if grad.norm()>=rho {
         Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit")))
     } else {
         Ok(x)
     }
 }
-
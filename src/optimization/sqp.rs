@@ -0,0 +1,191 @@
+use std::fmt;
+use crate::{
+    error::ConvOptError, error::ErrKind,
+    equation::{
+        equilibrated_regularized, forward_solve_multiple_in_place, back_solve_multiple_in_place
+    },
+    Result, DVec, DMat,
+    optimization::{MinProblem, ConstraintSet, backtracking_search}
+};
+
+
+
+/// Result of the SQP solver.
+///
+#[derive(Debug)]
+pub struct SqpResult {
+    /// primal solution
+    pub x: DVec,
+    /// Lagrange multipliers mu_i >= 0 of the constraints g_i(x) <= 0, in the order of the
+    /// constraints in the ConstraintSet
+    pub multipliers: DVec,
+    pub objF_x: f64,
+    /// max_i g_i(x)^+
+    pub max_violation: f64,
+    /// ||grad(f)(x) + sum_i mu_i*grad(g_i)(x)||_oo
+    pub stationarity: f64,
+    pub iterations: usize,
+}
+
+impl fmt::Display for SqpResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(format!(
+            "iterations: {0}, f(x): {1:.8}, max violation: {2:.4e}, stationarity: {3:.4e}\n\
+            x: {4:.6}\nmultipliers: {5:.6}",
+            self.iterations, self.objF_x, self.max_violation, self.stationarity,
+            self.x, self.multipliers
+        ).as_str())
+    }
+}
+
+
+/// Values g_i(x) and Jacobian (rows grad(g_i)(x)') of the constraints at x.
+///
+fn linearize_constraints(constraints: &ConstraintSet, x: &DVec) -> (DVec, DMat) {
+
+    let m = constraints.constraints.len();
+    let c = DVec::from_fn(m,|i,_| constraints.constraints[i].value(x));
+    let mut J = DMat::repeat(m,x.len(),0f64);
+    for (i,ct) in constraints.constraints.iter().enumerate() {
+        J.set_row(i,&ct.gradient(x).transpose());
+    }
+    (c,J)
+}
+
+
+/// Solves the quadratic program
+///     ? = argmin_p g'p+p'Bp/2  subject to  c+Jp <= 0
+/// for positive definite B through its dual
+///     ? = argmin_{mu >= 0} mu'M*mu/2+mu'q,   M = JB^{-1}J',  q = JB^{-1}g-c,
+/// which is solved by projected Gauss-Seidel iteration. With few constraints this is cheap.
+/// Returns (p,mu), p = -B^{-1}(g+J'mu).
+///
+fn solve_qp_subproblem(g: &DVec, B: &DMat, c: &DVec, J: &DMat) -> Result<(DVec,DVec)> {
+
+    let m = c.len();
+    let n = g.len();
+    // B^{-1}g and the columns B^{-1}J_i' with a single factorization G=LL' of the equilibrated
+    // matrix G=DBD: B^{-1}[g|J'] = DG^{-1}D[g|J']
+    let (d,G) = equilibrated_regularized(B,0f64);
+    let L = match G.cholesky() {
+        Some(ch) => ch.l(),
+        None => return Err(ConvOptError::new(ErrKind::CholeskyFailure("in solve_qp_subproblem")))
+    };
+    let mut Y = DMat::from_fn(n,m+1,|r,k| d[r]*if k == 0 { g[r] } else { J[(k-1,r)] });
+    forward_solve_multiple_in_place(&L,&mut Y,0f64)?;
+    back_solve_multiple_in_place(&L.transpose(),&mut Y,0f64)?;
+    for r in 0..n { Y.row_mut(r).scale_mut(d[r]); }
+    let Binv_g: DVec = Y.column(0).into_owned();
+    let Binv_Jt: DMat = Y.columns(1,m).into_owned();
+    let M = J*&Binv_Jt;
+    let q = J*&Binv_g-c;
+
+    let mut mu = DVec::repeat(m,0f64);
+    let mut sweep = 0;
+    while sweep < 10000 {
+
+        let mut change = 0f64;
+        for i in 0..m {
+
+            if M[(i,i)] <= 0f64 { continue; }
+            let r_i = M.row(i).transpose().dot(&mu)+q[i];
+            let mu_i = (mu[i]-r_i/M[(i,i)]).max(0f64);
+            change = change.max((mu_i-mu[i]).abs());
+            mu[i] = mu_i;
+        }
+        if !mu.iter().all(|u| u.is_finite()) || mu.amax() > 1e12 {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure(
+                "linearized constraints inconsistent in SQP subproblem"
+            )));
+        }
+        if change <= 1e-14*(1f64+mu.amax()) { break; }
+        sweep += 1;
+    }
+    let p = -(Binv_g+&Binv_Jt*&mu);
+    Ok((p,mu))
+}
+
+
+/// Minimizes the objective function f of `min_prob` subject to the constraints
+/// g_i(x) <= 0 in `constraints` by sequential quadratic programming: at the current iterate x
+/// with multiplier estimates mu the constraints are linearized and the step p solves the QP
+///     ? = argmin_p grad(f)'p+p'Bp/2  subject to  g_i(x)+grad(g_i)(x)'p <= 0,
+/// where B = hessian(f)+sum_i mu_i*hessian(g_i) is the Hessian of the Lagrangian (positive
+/// semidefinite for convex problems, slightly regularized). The QP multipliers are the new
+/// multiplier estimates. The step is globalized by backtracking line search on the L1 merit
+/// function f(x)+nu*sum_i g_i(x)^+, where the penalty nu is kept above max_i mu_i.
+///
+/// The iterates need not be feasible but must stay in the domain of f. The iteration stops
+/// when the KKT conditions hold up to eps: ||grad(f)+sum_i mu_i*grad(g_i)||_oo <= eps,
+/// g_i(x) <= eps and |mu_i*g_i(x)| <= eps.
+///
+/// # Arguments
+///
+/// * `eps`: tolerance for the KKT conditions.
+/// * `max_iter`: maximal number of SQP iterations.
+///
+pub fn sqp(
+    min_prob: &dyn MinProblem, constraints: &ConstraintSet, eps: f64, max_iter: usize
+) -> Result<SqpResult> {
+
+    let n = min_prob.dim();
+    let m = constraints.constraints.len();
+    assert!(constraints.dim==n,"dimension mismatch: dim(objective) = {}, dim(constraints) = {}",
+            n,constraints.dim);
+    let D = min_prob.domain();
+
+    let violation = |c: &DVec| -> f64 { c.iter().map(|u| u.max(0f64)).sum() };
+    let mut x = min_prob.start_point();
+    let mut mu = DVec::repeat(m,0f64);
+    let mut nu = 1f64;      // penalty parameter of the merit function
+
+    let mut iter = 0;
+    loop {
+        let g = min_prob.gradient(&x);
+        let (c,J) = linearize_constraints(constraints,&x);
+
+        // KKT conditions
+        let stationarity = (&g+J.tr_mul(&mu)).amax();
+        let max_violation = c.iter().fold(0f64,|a,u| a.max(*u));
+        let complementarity = mu.component_mul(&c).amax();
+        if stationarity <= eps && max_violation <= eps && (m == 0 || complementarity <= eps) {
+            return Ok(SqpResult{
+                objF_x: min_prob.objective_fn(&x), x, multipliers: mu, max_violation,
+                stationarity, iterations: iter
+            });
+        }
+        if iter == max_iter {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure("Max iterations hit in sqp")));
+        }
+
+        // Hessian of the Lagrangian
+        let mut B = min_prob.hessian(&x);
+        for (i,ct) in constraints.constraints.iter().enumerate() {
+            if mu[i] > 0f64 { B += mu[i]*ct.hessian(&x); }
+        }
+        let delta = 1e-8*(1f64+B.amax());
+        B += delta*DMat::identity(n,n);
+
+        let (p,mu_qp) = solve_qp_subproblem(&g,&B,&c,&J)?;
+        if !mu_qp.is_empty() { nu = nu.max(1.1f64*mu_qp.amax()+1e-8); }
+
+        // line search on the L1 merit function
+        let merit = |z: &DVec| -> f64 {
+            if !D.contains(z) { return f64::INFINITY; }
+            let c_z = DVec::from_fn(m,|i,_| constraints.constraints[i].value(z));
+            min_prob.objective_fn(z)+nu*violation(&c_z)
+        };
+        let merit_x = merit(&x);
+        let slope = g.dot(&p)-nu*violation(&c);
+        let phi = |t: f64| merit(&(&x+t*&p));
+        let ls_result = backtracking_search(&phi,merit_x,slope.min(-1e-16),1e-4,0.5,60);
+        if !ls_result.converged {
+            return Err(ConvOptError::new(ErrKind::ConvergenceFailure(
+                "no decrease of the merit function in sqp"
+            )));
+        }
+        x += ls_result.t*&p;
+        mu = mu_qp;
+        iter += 1;
+    }
+}
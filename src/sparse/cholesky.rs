@@ -0,0 +1,334 @@
+use std::collections::BTreeSet;
+use crate::{
+    Result, DVec,
+    error::*,
+    sparse::CscMatrix
+};
+
+
+
+/// Fill reducing ordering of the symmetric matrix A by the approximate minimum degree (AMD)
+/// heuristic of Amestoy, Davis and Duff. The elimination graph is represented implicitly
+/// as a quotient graph: eliminating the variable p does not form the clique of its
+/// neighbours but turns p into an element whose variable list L_p is the union of the
+/// neighbours of p and the variable lists of the elements adjacent to p (these elements
+/// are absorbed into p). The storage therefore never exceeds the pattern of A.
+///
+/// The exact degree of a variable i (the size of the union of its neighbours and the
+/// lists of its elements) is replaced by the upper bound
+/// $\min(d_{old}+|L_p\setminus i|,\ |A_i|+|L_p\setminus i|+\sum_{e\neq p}|L_e\setminus L_p|)$,
+/// where the sizes $|L_e\setminus L_p|$ are obtained for all elements at once in a single
+/// pass over L_p. Ties are broken by the smallest index. Supervariable detection and
+/// aggressive absorption are not implemented.
+///
+/// Only the sparsity pattern of A+A' off the diagonal is used.
+/// Returns perm with perm[k] = the variable eliminated in step k.
+///
+pub fn amd_ordering(A: &CscMatrix) -> Vec<usize> {
+
+    let n = A.nrows;
+    assert!(A.ncols==n,"matrix A not square, rows={}, cols={}",n,A.ncols);
+
+    // variable neighbours A_i
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for j in 0..n {
+        for k in A.col_ptr[j]..A.col_ptr[j+1] {
+            let i = A.row_idx[k];
+            if i != j { adj[i].push(j); adj[j].push(i); }
+        }
+    }
+    for a in adj.iter_mut() { a.sort_unstable(); a.dedup(); }
+    // elements E_i adjacent to each variable, elements are named by their pivot
+    let mut elems: Vec<Vec<usize>> = vec![Vec::new(); n];
+    // variable lists L_e of the elements
+    let mut vars: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut eliminated = vec![false; n];
+    let mut absorbed = vec![false; n];
+    let mut degree: Vec<usize> = adj.iter().map(|a| a.len()).collect();
+    // mark[i] == k: i is in L_p in step k
+    let mut mark = vec![usize::MAX; n];
+    // w[e] = |L_e \ L_p| for the elements adjacent to L_p, -1 otherwise
+    let mut w = vec![-1isize; n];
+    // queue of (degree, variable)
+    let mut queue: BTreeSet<(usize,usize)> = (0..n).map(|v| (degree[v],v)).collect();
+    let mut perm = Vec::with_capacity(n);
+
+    for k in 0..n {
+
+        let (deg,p) = *queue.iter().next().unwrap();
+        queue.remove(&(deg,p));
+        perm.push(p);
+        eliminated[p] = true;
+        mark[p] = k;
+
+        // L_p, absorbing the elements adjacent to p
+        let mut lp = Vec::new();
+        for &i in adj[p].iter() {
+            if mark[i] != k { mark[i] = k; lp.push(i); }
+        }
+        for &e in elems[p].iter() {
+            for &i in vars[e].iter() {
+                if !eliminated[i] && mark[i] != k { mark[i] = k; lp.push(i); }
+            }
+            vars[e] = Vec::new();
+            absorbed[e] = true;
+        }
+        adj[p] = Vec::new();
+        elems[p] = Vec::new();
+
+        // |L_e \ L_p| for all elements e adjacent to some variable in L_p
+        let mut touched = Vec::new();
+        for &i in lp.iter() {
+            for &e in elems[i].iter() {
+                if absorbed[e] { continue; }
+                if w[e] < 0 { w[e] = vars[e].len() as isize; touched.push(e); }
+                w[e] -= 1;
+            }
+        }
+
+        // update the quotient graph and the approximate degrees of the variables in L_p
+        let remaining = (n-k-1).saturating_sub(1);
+        let lpi = lp.len().saturating_sub(1);
+        for &i in lp.iter() {
+
+            elems[i].retain(|&e| !absorbed[e]);
+            // neighbours in L_p (and p itself) are now covered by the element p
+            adj[i].retain(|&j| mark[j] != k);
+            let external: usize = elems[i].iter().map(|&e| w[e] as usize).sum();
+            elems[i].push(p);
+            let d = remaining.min(degree[i]+lpi).min(adj[i].len()+lpi+external);
+            queue.remove(&(degree[i],i));
+            degree[i] = d;
+            queue.insert((d,i));
+        }
+        for &e in touched.iter() { w[e] = -1; }
+        vars[p] = lp;
+    }
+    perm
+}
+
+
+
+/// Symbolic Cholesky factorization of a sparse symmetric positive definite matrix A:
+/// the fill reducing permutation P and the sparsity pattern of the Cholesky factor L of
+/// C = PAP' (C_ij = A_{perm[i],perm[j]}), computed from the elimination tree of C.
+///
+/// The symbolic factorization depends only on the sparsity pattern of A, so it can be
+/// reused for all matrices with the same pattern (e.g. the Hessians at all iterates of
+/// a Newton method, or A+lI for different l).
+///
+#[derive(Clone,Debug)]
+pub struct SymbolicCholesky {
+    pub n: usize,
+    /// perm[k] = row/column of A which becomes row/column k of C
+    pub perm: Vec<usize>,
+    /// inverse permutation
+    pub inv_perm: Vec<usize>,
+    /// parent of each column in the elimination tree of C (None for roots)
+    pub parent: Vec<Option<usize>>,
+    /// column pointers of L (CSC), the diagonal entry is the first entry in each column
+    pub l_col_ptr: Vec<usize>,
+    pub l_row_idx: Vec<usize>,
+    /// the strictly lower triangular pattern of L by rows: the column indices of row k
+    /// are l_row_pattern[l_row_ptr[k]..l_row_ptr[k+1]], increasing
+    l_row_ptr: Vec<usize>,
+    l_row_pattern: Vec<usize>,
+    /// pattern of A the analysis was done for
+    a_col_ptr: Vec<usize>,
+    a_row_idx: Vec<usize>,
+}
+
+impl SymbolicCholesky {
+
+    /// Symbolic factorization with the approximate minimum degree ordering of A.
+    /// A must be symmetric with both triangles stored.
+    pub fn analyze(A: &CscMatrix) -> SymbolicCholesky {
+
+        let perm = amd_ordering(A);
+        SymbolicCholesky::analyze_with_ordering(A,perm)
+    }
+
+    /// Symbolic factorization with the given ordering (perm[k] = row/column of A which
+    /// becomes row/column k).
+    pub fn analyze_with_ordering(A: &CscMatrix, perm: Vec<usize>) -> SymbolicCholesky {
+
+        let n = A.nrows;
+        assert!(A.ncols==n && perm.len()==n);
+        let mut inv_perm = vec![0usize; n];
+        for (k,&p) in perm.iter().enumerate() { inv_perm[p] = k; }
+
+        // elimination tree of C (Liu's algorithm with path compression)
+        let mut parent: Vec<Option<usize>> = vec![None; n];
+        let mut ancestor: Vec<Option<usize>> = vec![None; n];
+        for (k,&j) in perm.iter().enumerate() {
+            for p in A.col_ptr[j]..A.col_ptr[j+1] {
+
+                let mut i = inv_perm[A.row_idx[p]];
+                if i >= k { continue; }
+                // climb from i to the root of its subtree, pointing all nodes to k
+                while let Some(a) = ancestor[i] {
+                    if a == k { break; }
+                    ancestor[i] = Some(k);
+                    i = a;
+                }
+                if ancestor[i].is_none() { ancestor[i] = Some(k); parent[i] = Some(k); }
+            }
+        }
+
+        // row patterns of L: row k has nonzeros in the columns of the subtrees of the
+        // elimination tree reached from the nonzeros C_ik, i<k
+        let mut mark = vec![usize::MAX; n];
+        let mut l_row_ptr = Vec::with_capacity(n+1);
+        let mut l_row_pattern = Vec::new();
+        let mut col_counts = vec![1usize; n];   // diagonal
+        l_row_ptr.push(0);
+        for k in 0..n {
+
+            mark[k] = k;
+            let start = l_row_pattern.len();
+            let j = perm[k];
+            for p in A.col_ptr[j]..A.col_ptr[j+1] {
+
+                let mut i = inv_perm[A.row_idx[p]];
+                if i >= k { continue; }
+                while mark[i] != k {
+                    l_row_pattern.push(i);
+                    col_counts[i] += 1;
+                    mark[i] = k;
+                    i = parent[i].unwrap();
+                }
+            }
+            l_row_pattern[start..].sort_unstable();
+            l_row_ptr.push(l_row_pattern.len());
+        }
+
+        // column pattern of L
+        let mut l_col_ptr = Vec::with_capacity(n+1);
+        l_col_ptr.push(0);
+        for k in 0..n { l_col_ptr.push(l_col_ptr[k]+col_counts[k]); }
+        let mut l_row_idx = vec![0usize; l_col_ptr[n]];
+        let mut next: Vec<usize> = l_col_ptr[..n].to_vec();
+        for k in 0..n {
+            l_row_idx[next[k]] = k;
+            next[k] += 1;
+            for &i in l_row_pattern[l_row_ptr[k]..l_row_ptr[k+1]].iter() {
+                l_row_idx[next[i]] = k;
+                next[i] += 1;
+            }
+        }
+        SymbolicCholesky{
+            n, perm, inv_perm, parent, l_col_ptr, l_row_idx, l_row_ptr, l_row_pattern,
+            a_col_ptr: A.col_ptr.clone(), a_row_idx: A.row_idx.clone()
+        }
+    }
+
+    /// number of nonzeros in the Cholesky factor L
+    pub fn nnz_l(&self) -> usize { self.l_row_idx.len() }
+
+    /// true if A has the sparsity pattern this symbolic factorization was computed for.
+    pub fn matches_pattern(&self, A: &CscMatrix) -> bool {
+        A.col_ptr == self.a_col_ptr && A.row_idx == self.a_row_idx
+    }
+
+    /// Numeric Cholesky factorization LL' = P(A+lI)P' (up-looking, row by row).
+    /// A must have the sparsity pattern of the analyzed matrix and A+lI must be
+    /// positive definite.
+    pub fn factor(&self, A: &CscMatrix, l: f64) -> Result<SparseCholesky<'_>> {
+
+        assert!(self.matches_pattern(A),"sparsity pattern differs from the analyzed matrix");
+        let n = self.n;
+        let mut values = vec![0f64; self.nnz_l()];
+        let mut fill: Vec<usize> = self.l_col_ptr[..n].iter().map(|&p| p+1).collect();
+        let mut x = vec![0f64; n];
+
+        for k in 0..n {
+
+            // scatter the upper triangular part of column k of C+lI into x
+            let j = self.perm[k];
+            for p in A.col_ptr[j]..A.col_ptr[j+1] {
+                let i = self.inv_perm[A.row_idx[p]];
+                if i <= k { x[i] += A.values[p]; }
+            }
+            let mut d = x[k]+l;
+            x[k] = 0f64;
+            for &i in self.l_row_pattern[self.l_row_ptr[k]..self.l_row_ptr[k+1]].iter() {
+
+                // L_ki = (x_i - sum_{j<i} L_ij*L_kj)/L_ii, the sum has been subtracted already
+                let l_ki = x[i]/values[self.l_col_ptr[i]];
+                x[i] = 0f64;
+                for p in self.l_col_ptr[i]+1..fill[i] {
+                    x[self.l_row_idx[p]] -= values[p]*l_ki;
+                }
+                d -= l_ki*l_ki;
+                values[fill[i]] = l_ki;
+                fill[i] += 1;
+            }
+            if d <= 0f64 || !d.is_finite() {
+                return Err(ConvOptError::new(ErrKind::CholeskyFailure(
+                    "sparse matrix not positive definite"
+                )));
+            }
+            values[self.l_col_ptr[k]] = d.sqrt();
+        }
+        Ok(SparseCholesky{ symbolic: self, values })
+    }
+}
+
+
+
+/// Numeric sparse Cholesky factor LL' = P(A+lI)P' with the pattern of L
+/// given by the symbolic factorization.
+///
+#[derive(Clone,Debug)]
+pub struct SparseCholesky<'a> {
+    pub symbolic: &'a SymbolicCholesky,
+    /// values of L in the pattern symbolic.l_col_ptr, symbolic.l_row_idx
+    pub values: Vec<f64>,
+}
+
+impl<'a> SparseCholesky<'a> {
+
+    /// Solves (A+lI)x=b.
+    pub fn solve(&self, b: &DVec) -> DVec {
+
+        let s = self.symbolic;
+        let n = s.n;
+        assert!(b.len()==n);
+        let mut y: Vec<f64> = (0..n).map(|k| b[s.perm[k]]).collect();
+
+        // Lz=y
+        for j in 0..n {
+            let p0 = s.l_col_ptr[j];
+            y[j] /= self.values[p0];
+            let y_j = y[j];
+            for p in p0+1..s.l_col_ptr[j+1] { y[s.l_row_idx[p]] -= self.values[p]*y_j; }
+        }
+        // L'w=z
+        for j in (0..n).rev() {
+            let p0 = s.l_col_ptr[j];
+            let mut t = y[j];
+            for p in p0+1..s.l_col_ptr[j+1] { t -= self.values[p]*y[s.l_row_idx[p]]; }
+            y[j] = t/self.values[p0];
+        }
+        let mut x = DVec::repeat(n,0f64);
+        for k in 0..n { x[s.perm[k]] = y[k]; }
+        x
+    }
+}
+
+
+
+/// Solves the regularized equation (A+l*I)x=b for a sparse symmetric matrix A (both
+/// triangles stored) by sparse Cholesky factorization with approximate minimum degree
+/// ordering. A+l*I must be positive definite.
+///
+/// To solve many systems with the same sparsity pattern use SymbolicCholesky::analyze
+/// once and SymbolicCholesky::factor for each matrix.
+///
+pub fn sparse_cholesky_solve(A: &CscMatrix, b: &DVec, l: f64) -> Result<DVec> {
+
+    assert!(A.nrows==A.ncols && A.nrows==b.len() && l >= 0f64);
+    let symbolic = SymbolicCholesky::analyze(A);
+    let ch = symbolic.factor(A,l)?;
+    Ok(ch.solve(b))
+}
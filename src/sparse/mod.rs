@@ -0,0 +1,244 @@
+pub use self::cholesky::*;
+use crate::{DVec, DMat};
+
+
+mod cholesky;
+
+
+
+/// Sparse matrix in compressed sparse column (CSC) format: the row indices and values of
+/// the nonzero entries of column j are row_idx[k], values[k] for col_ptr[j] <= k < col_ptr[j+1],
+/// the row indices in each column are strictly increasing.
+///
+/// Used for Hessians and constraint matrices of large problems where dense matrices
+/// cannot be formed.
+///
+#[derive(Clone,Debug)]
+pub struct CscMatrix {
+    pub nrows: usize,
+    pub ncols: usize,
+    pub col_ptr: Vec<usize>,
+    pub row_idx: Vec<usize>,
+    pub values: Vec<f64>,
+}
+
+impl CscMatrix {
+
+    pub fn new(
+        nrows: usize, ncols: usize, col_ptr: Vec<usize>, row_idx: Vec<usize>, values: Vec<f64>
+    ) -> CscMatrix {
+
+        assert!(col_ptr.len()==ncols+1 && col_ptr[0]==0 && col_ptr[ncols]==row_idx.len(),
+                "inconsistent column pointers");
+        assert!(row_idx.len()==values.len());
+        for j in 0..ncols {
+            let rows = &row_idx[col_ptr[j]..col_ptr[j+1]];
+            assert!(rows.windows(2).all(|w| w[0] < w[1]) && rows.iter().all(|&i| i < nrows),
+                    "row indices in column {} not strictly increasing or out of range",j);
+        }
+        CscMatrix{ nrows, ncols, col_ptr, row_idx, values }
+    }
+
+    /// Matrix from the entries (i,j,a_ij), duplicate entries are summed.
+    pub fn from_triplets(nrows: usize, ncols: usize, triplets: &[(usize,usize,f64)]) -> CscMatrix {
+
+        let mut columns: Vec<Vec<(usize,f64)>> = vec![Vec::new(); ncols];
+        for &(i,j,a) in triplets.iter() {
+            assert!(i < nrows && j < ncols,"entry ({},{}) out of range",i,j);
+            columns[j].push((i,a));
+        }
+        let mut col_ptr = Vec::with_capacity(ncols+1);
+        let mut row_idx = Vec::with_capacity(triplets.len());
+        let mut values = Vec::with_capacity(triplets.len());
+        col_ptr.push(0);
+        for col in columns.iter_mut() {
+
+            col.sort_by_key(|&(i,_)| i);
+            for &(i,a) in col.iter() {
+                if row_idx.len() > *col_ptr.last().unwrap() && *row_idx.last().unwrap() == i {
+                    *values.last_mut().unwrap() += a;
+                } else {
+                    row_idx.push(i);
+                    values.push(a);
+                }
+            }
+            col_ptr.push(row_idx.len());
+        }
+        CscMatrix{ nrows, ncols, col_ptr, row_idx, values }
+    }
+
+    /// Sparse copy of A, entries with |a_ij| <= drop_tol are dropped.
+    pub fn from_dense(A: &DMat, drop_tol: f64) -> CscMatrix {
+
+        let (m,n) = A.shape();
+        let mut col_ptr = Vec::with_capacity(n+1);
+        let mut row_idx = Vec::new();
+        let mut values = Vec::new();
+        col_ptr.push(0);
+        for j in 0..n {
+            for i in 0..m {
+                let a = A[(i,j)];
+                if a.abs() > drop_tol { row_idx.push(i); values.push(a); }
+            }
+            col_ptr.push(row_idx.len());
+        }
+        CscMatrix{ nrows: m, ncols: n, col_ptr, row_idx, values }
+    }
+
+    pub fn identity(n: usize) -> CscMatrix {
+        CscMatrix{
+            nrows: n, ncols: n, col_ptr: (0..=n).collect(), row_idx: (0..n).collect(),
+            values: vec![1f64; n]
+        }
+    }
+
+    pub fn to_dense(&self) -> DMat {
+
+        let mut A = DMat::repeat(self.nrows,self.ncols,0f64);
+        for j in 0..self.ncols {
+            for k in self.col_ptr[j]..self.col_ptr[j+1] { A[(self.row_idx[k],j)] = self.values[k]; }
+        }
+        A
+    }
+
+    /// number of stored entries
+    pub fn nnz(&self) -> usize { self.values.len() }
+
+    /// entry a_ij (zero if not stored)
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+
+        let rows = &self.row_idx[self.col_ptr[j]..self.col_ptr[j+1]];
+        match rows.binary_search(&i) {
+            Ok(k) => self.values[self.col_ptr[j]+k],
+            Err(_) => 0f64
+        }
+    }
+
+    /// Ax
+    pub fn mul_vec(&self, x: &DVec) -> DVec {
+
+        assert!(x.len()==self.ncols);
+        let mut y = DVec::repeat(self.nrows,0f64);
+        for j in 0..self.ncols {
+            let x_j = x[j];
+            for k in self.col_ptr[j]..self.col_ptr[j+1] { y[self.row_idx[k]] += self.values[k]*x_j; }
+        }
+        y
+    }
+
+    /// A'x
+    pub fn tr_mul_vec(&self, x: &DVec) -> DVec {
+
+        assert!(x.len()==self.nrows);
+        DVec::from_fn(self.ncols,|j,_|
+            (self.col_ptr[j]..self.col_ptr[j+1]).map(|k| self.values[k]*x[self.row_idx[k]]).sum()
+        )
+    }
+
+    pub fn transpose(&self) -> CscMatrix {
+
+        let mut counts = vec![0usize; self.nrows+1];
+        for &i in self.row_idx.iter() { counts[i+1] += 1; }
+        for i in 0..self.nrows { counts[i+1] += counts[i]; }
+        let col_ptr = counts.clone();
+        let mut next = counts;
+        let mut row_idx = vec![0usize; self.nnz()];
+        let mut values = vec![0f64; self.nnz()];
+        for j in 0..self.ncols {
+            for k in self.col_ptr[j]..self.col_ptr[j+1] {
+                let i = self.row_idx[k];
+                row_idx[next[i]] = j;
+                values[next[i]] = self.values[k];
+                next[i] += 1;
+            }
+        }
+        CscMatrix{ nrows: self.ncols, ncols: self.nrows, col_ptr, row_idx, values }
+    }
+
+    pub fn diagonal(&self) -> DVec {
+        DVec::from_fn(self.nrows.min(self.ncols),|i,_| self.get(i,i))
+    }
+
+    /// The matrix diag(d_r)*A*diag(d_c).
+    pub fn scale(&self, d_r: &DVec, d_c: &DVec) -> CscMatrix {
+
+        assert!(d_r.len()==self.nrows && d_c.len()==self.ncols);
+        let mut B = self.clone();
+        for j in 0..self.ncols {
+            for k in self.col_ptr[j]..self.col_ptr[j+1] {
+                B.values[k] *= d_r[self.row_idx[k]]*d_c[j];
+            }
+        }
+        B
+    }
+
+    /// Maximum of |a_ij| in each row.
+    pub fn row_amax(&self) -> DVec {
+
+        let mut r = DVec::repeat(self.nrows,0f64);
+        for (k,&i) in self.row_idx.iter().enumerate() { r[i] = r[i].max(self.values[k].abs()); }
+        r
+    }
+
+    /// Euclidean norm of each row.
+    pub fn row_norms(&self) -> DVec {
+
+        let mut r = DVec::repeat(self.nrows,0f64);
+        for (k,&i) in self.row_idx.iter().enumerate() { r[i] += self.values[k]*self.values[k]; }
+        r.map(|u| u.sqrt())
+    }
+}
+
+
+
+/// Ruiz equilibration B = DAD of a sparse symmetric matrix, see
+/// matrix_utils::ruiz_equilibration. Only the stored entries are rescaled in each round,
+/// so a round costs O(nnz(A)).
+///
+/// Returns tuple (d,B).
+///
+pub fn sparse_ruiz_equilibration(A: &CscMatrix, n_oo: usize, n_2: usize) -> (DVec, CscMatrix) {
+
+    let n = A.nrows;
+    assert!(A.ncols==n,"matrix A not square, rows={}, cols={}",n,A.ncols);
+
+    let mut d: DVec = DVec::repeat(n,1f64);
+    let mut B = A.clone();
+    let mut k = 0;
+    while k < n_oo+n_2 {
+
+        let f = if k < n_oo { B.row_amax() } else { B.row_norms() };
+        let s = f.map(|u| if u > 0f64 { 1f64/u.sqrt() } else { 1f64 });
+        d.component_mul_assign(&s);
+        B = B.scale(&s,&s);
+        k += 1;
+    }
+    (d,B)
+}
+
+
+/// Two-sided Ruiz equilibration $B=D_rAD_c$ of a sparse rectangular matrix, see
+/// matrix_utils::two_sided_ruiz_equilibration. A round costs O(nnz(A)).
+///
+/// Returns tuple (d_r,d_c,B).
+///
+pub fn sparse_two_sided_ruiz_equilibration(A: &CscMatrix, n_iter: usize) -> (DVec, DVec, CscMatrix) {
+
+    let mut d_r: DVec = DVec::repeat(A.nrows,1f64);
+    let mut d_c: DVec = DVec::repeat(A.ncols,1f64);
+    let mut B = A.clone();
+    let inv_sqrt = |u: f64| if u > 0f64 { 1f64/u.sqrt() } else { 1f64 };
+    let mut k = 0;
+    while k < n_iter {
+
+        let s_r = B.row_amax().map(inv_sqrt);
+        let s_c = DVec::from_fn(A.ncols,|j,_|
+            inv_sqrt(B.values[B.col_ptr[j]..B.col_ptr[j+1]].iter().fold(0f64,|a,b| a.max(b.abs())))
+        );
+        d_r.component_mul_assign(&s_r);
+        d_c.component_mul_assign(&s_c);
+        B = B.scale(&s_r,&s_c);
+        k += 1;
+    }
+    (d_r,d_c,B)
+}
@@ -2,7 +2,8 @@ use crate::{
     error::ConvOptError, error::ErrKind,
     Result, DVec, DMat, FUN_nD_TO_1D,
     logging::Logger,
-    optimization::{Region, WholeSpace, AllPositive, MinProblem}
+    optimization::{Region, WholeSpace, AllPositive, MinProblem, NonsmoothProblem},
+    equation::StructuredMatrix
 };
 
 
@@ -44,6 +45,18 @@ impl MinProblem for Maxent {
             |i,j| if i==j { 1f64/x[i]+20f64 } else { 20f64 }
         )
     }
+    fn partial_derivative(&self,x: &DVec,i: usize) -> f64 {
+        1f64+x[i].ln()-20f64*(1f64-x.sum())
+    }
+    fn hessian_diagonal(&self,x: &DVec) -> DVec { x.map(|u| 1f64/u+20f64) }
+    /// diag(1/x)+20*11'
+    fn structured_hessian(&self,x: &DVec) -> Option<StructuredMatrix> {
+        Some(StructuredMatrix::DiagonalPlusLowRank{
+            d: x.map(|u| 1f64/u),
+            U: DMat::repeat(self.dim,1,1f64),
+            C: DMat::repeat(1,1,20f64),
+        })
+    }
     fn domain(&self) -> &dyn Region { &(self.G) }
 }
 
@@ -95,4 +108,66 @@ impl MinProblem for Rosenbrook {
         ])
     }
     fn domain(&self) -> &dyn Region { &(self.G) }
-}
\ No newline at end of file
+}
+
+
+/// Linear least squares f(x) = ||Ax-b||²/2 on the whole space, starting at x=0.
+/// With a nonsmooth penalty (e.g. L1Norm) this is the smooth part of the lasso problem.
+///
+pub struct LeastSquares {
+    A: DMat,
+    b: DVec,
+    G: WholeSpace,
+}
+impl LeastSquares {
+    pub fn new(A: DMat, b: DVec) -> LeastSquares {
+        assert!(A.nrows()==b.len());
+        let n = A.ncols();
+        LeastSquares { A, b, G: WholeSpace::new(n) }
+    }
+}
+impl MinProblem for LeastSquares {
+
+    fn id(&self) -> String {
+        format!("LeastSquaresProblem({}x{})",self.A.nrows(),self.A.ncols())
+    }
+    fn dim(&self) -> usize { self.A.ncols() }
+    fn start_point(&self) -> DVec { DVec::repeat(self.dim(),0f64) }
+    fn objective_fn(&self,x: &DVec) -> f64 {
+        0.5f64*(&self.A*x-&self.b).norm_squared()
+    }
+    fn gradient(&self,x: &DVec) -> DVec {
+        self.A.tr_mul(&(&self.A*x-&self.b))
+    }
+    fn hessian(&self,x: &DVec) -> DMat { self.A.tr_mul(&self.A) }
+    fn domain(&self) -> &dyn Region { &(self.G) }
+}
+
+
+
+/// Piecewise linear f(x) = max_i (a_i'x+b_i), a_i the rows of A, starting at x=0.
+/// A subgradient at x is a_i for any i attaining the maximum.
+///
+pub struct MaxOfAffine {
+    A: DMat,
+    b: DVec,
+}
+impl MaxOfAffine {
+    pub fn new(A: DMat, b: DVec) -> MaxOfAffine {
+        assert!(A.nrows()==b.len());
+        MaxOfAffine { A, b }
+    }
+}
+impl NonsmoothProblem for MaxOfAffine {
+
+    fn id(&self) -> String {
+        format!("MaxOfAffineProblem({} pieces, dim={})",self.A.nrows(),self.A.ncols())
+    }
+    fn dim(&self) -> usize { self.A.ncols() }
+    fn start_point(&self) -> DVec { DVec::repeat(self.dim(),0f64) }
+    fn objective_fn(&self,x: &DVec) -> f64 { (&self.A*x+&self.b).max() }
+    fn subgradient(&self,x: &DVec) -> DVec {
+        let i = (&self.A*x+&self.b).imax();
+        self.A.row(i).transpose()
+    }
+}
@@ -3,6 +3,7 @@ use convopt::{
     matrix_utils::*,
     equation::*
 };
+use nalgebra::{DMatrix, DVector};
 use rand_xoshiro::{Xoshiro256PlusPlus, rand_core::SeedableRng};
 
 #[test]
@@ -24,11 +25,360 @@ fn test_cholesky_solve() {
         assert!(false,"Cholesky decomposition failed");
     }
     println!("Solution via Cholesky factorization, forward_solve and back_solve:");
-    let chol_A = A.cholesky().unwrap();
+    let chol_A = A.clone().cholesky().unwrap();
     let L: DMat = chol_A.l();
     let U: DMat = L.transpose();
-    let w = forward_solve(&L,&b,0f64)?;
-    let x = back_solve(&U,&w,0f64)?;
+    let w = forward_solve(&L,&b,0f64).unwrap();
+    let x = back_solve(&U,&w,0f64).unwrap();
     let residual = &b - &A*x;
     assert!(residual.norm() < 1e-12*&A.norm());
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_ldlt_solve() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(41);
+    let n = 60usize;
+    let m = 20usize;
+
+    // symmetric indefinite matrix with known inertia
+    let Q: DMat = random_orthogonal_matrix(n,&mut rng);
+    let ev = DVec::from_fn(n,|i,_| if i < 35 { 1f64+i as f64 } else { -1f64-i as f64 });
+    let S = &Q*DMat::from_diagonal(&ev)*Q.transpose();
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+
+    let ldlt = LDLT::new(&S);
+    let P = DMat::from_fn(n,n,|i,j| if ldlt.perm[i]==j { 1f64 } else { 0f64 });
+    let E = &P*&S*P.transpose() - &ldlt.L*&ldlt.D*ldlt.L.transpose();
+    assert!(E.amax() < 1e-10*S.amax());
+    assert_eq!(ldlt.inertia(1e-12),Inertia{ positive: 35, negative: 25, zero: 0 });
+
+    let (x,inertia) = ldlt_solve(&S,&b,0f64).unwrap();
+    assert!((&b - &S*x).norm() < 1e-10*S.norm());
+    assert_eq!(inertia,Inertia{ positive: 35, negative: 25, zero: 0 });
+
+    // KKT system with positive definite H
+    let H = random_psd_matrix(n,0.1f64,10f64,&mut rng);
+    let A = random_matrix(m,n,-1f64,1f64,&mut rng);
+    let g = random_vector(n,-1f64,1f64,&mut rng);
+    let h = random_vector(m,-1f64,1f64,&mut rng);
+    let (x,y,inertia) = kkt_solve(&H,&A,&g,&h).unwrap();
+    assert!((&H*&x + A.transpose()*&y - &g).norm() < 1e-9);
+    assert!((&A*&x - &h).norm() < 1e-9);
+    assert_eq!(inertia,Inertia{ positive: n, negative: m, zero: 0 });
+}
+
+
+#[test]
+fn test_modified_cholesky() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(43);
+    let n = 50usize;
+
+    // positive definite: no modification
+    let A = random_psd_matrix(n,0.1f64,10f64,&mut rng);
+    let mc = ModifiedCholesky::new(&A);
+    assert_eq!(mc.shift(),0f64);
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+    let (x,shift) = modified_cholesky_solve(&A,&b,0f64).unwrap();
+    assert_eq!(shift,0f64);
+    assert!((&b - &A*x).norm() < 1e-10*A.norm());
+
+    // singular and indefinite: A+E = LDL' with positive D
+    let Q: DMat = random_orthogonal_matrix(n,&mut rng);
+    let ev = DVec::from_fn(n,|i,_| if i < 10 { 0f64 } else if i < 20 { -1f64 } else { 1f64 });
+    let S = &Q*DMat::from_diagonal(&ev)*Q.transpose();
+    let mc = ModifiedCholesky::new(&S);
+    assert!(mc.shift() > 0f64);
+    assert!(mc.D.iter().all(|&d| d > 0f64));
+    let E = &S + DMat::from_diagonal(&mc.E) - &mc.L*DMat::from_diagonal(&mc.D)*mc.L.transpose();
+    assert!(E.amax() < 1e-10*(1f64+mc.shift()));
+    let (x,shift) = modified_cholesky_solve(&S,&b,0f64).unwrap();
+    assert!(shift > 0f64 && x.iter().all(|x_i| x_i.is_finite()));
+}
+
+
+#[test]
+fn test_regularized_solver() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(47);
+    let n = 80usize;
+    let A = random_psd_matrix(n,0.01f64,100f64,&mut rng);
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+
+    let solver = RegularizedSolver::new(&A);
+    for &l in [0f64, 1e-4, 1e-2, 1f64].iter() {
+        let x = solver.solve(&b,l).unwrap();
+        let y = cholesky_solve_regularized(&A,&b,l).unwrap();
+        assert!((&x-&y).norm() < 1e-8*(1f64+y.norm()));
+    }
+    let S = -A;
+    assert!(RegularizedSolver::new(&S).solve(&b,0f64).is_err());
+}
+
+
+#[test]
+fn test_refined_solve() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(53);
+    let n = 100usize;
+    let A = random_psd_matrix(n,1e-9f64,1000f64,&mut rng);
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+
+    let plain = cholesky_solve_refined(&A,&b,0f64,0).unwrap();
+    let refined = cholesky_solve_refined(&A,&b,0f64,5).unwrap();
+    assert_eq!(plain.refinement_steps,0);
+    assert!(refined.backward_error <= plain.backward_error);
+    assert!(refined.backward_error < 1e-14);
+    let naive_residual = (&b - &A*&refined.x).norm();
+    assert!(naive_residual <= refined.residual_norm+1e-12*A.norm()*refined.x.norm());
+
+    let refined = qr_solve_refined(&A,&b,1e-6,5).unwrap();
+    assert!(refined.backward_error < 1e-14);
+    let x = qr_solve(&A,&b,1e-6).unwrap();
+    assert!((&refined.x-&x).norm() < 1e-6*x.norm());
+}
+
+
+#[test]
+fn test_condition_estimates() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(61);
+    let n = 100usize;
+    let A = random_psd_matrix(n,0.001f64,1000f64,&mut rng);
+
+    // exact 1-norm condition number
+    let A_inv = A.clone().try_inverse().unwrap();
+    let cond_1 = norm_1(&A)*norm_1(&A_inv);
+    let est = cholesky_condition_estimate(&A,&A.clone().cholesky().unwrap());
+    assert!(est <= cond_1*(1f64+1e-8) && est >= cond_1/3f64);
+    let est = lu_condition_estimate(&A,&A.clone().lu());
+    assert!(est <= cond_1*(1f64+1e-8) && est >= cond_1/3f64);
+
+    let (l_min,l_max) = lanczos_extreme_eigenvalues(&A,n);
+    assert!((l_max-1000f64).abs() < 1e-6 && (l_min-0.001f64).abs() < 1e-6);
+    let cond_2 = lanczos_condition_estimate(&A,40);
+    assert!(cond_2 <= 1e6*(1f64+1e-8) && cond_2 > 1e3);
+
+    // automatic choice of the solver
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+    let (x,solver,cond) = auto_solve(&A,&b,0f64).unwrap();
+    assert_eq!(solver,"Cholesky");
+    assert!(cond < COND_MAX_CHOLESKY);
+    assert!((&b-&A*x).norm() < 1e-8*A.norm());
+    // not positive definite: no fallback to QR
+    assert!(auto_solve(&(-&A),&b,0f64).is_err());
+}
+
+
+#[test]
+fn test_equilibration_strategies() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(67);
+    let n = 60usize;
+    let A0 = random_psd_matrix(n,0.01f64,100f64,&mut rng);
+    let s = DVec::from_fn(n,|i,_| 10f64.powi((i%7) as i32-3));
+    let A = DMat::from_fn(n,n,|r,c| s[r]*s[c]*A0[(r,c)]);
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+    let cond_A = cholesky_condition_estimate(&A,&A.clone().cholesky().unwrap());
+
+    let strategies: Vec<Box<dyn Equilibration>> = vec![
+        Box::new(Ruiz::default()),
+        Box::new(RuizConvergent::new(1e-6,200)),
+        Box::new(JacobiScaling{}),
+        Box::new(SinkhornKnopp::new(1e-6,500)),
+        Box::new(GeometricMean::new(5)),
+    ];
+    for eq in strategies.iter() {
+
+        let (d,B) = eq.equilibrate(&A);
+        assert!(d.iter().all(|&d_i| d_i > 0f64),"{}",eq.id());
+        let cond_B = cholesky_condition_estimate(&B,&B.clone().cholesky().unwrap());
+        assert!(cond_B < 1e-3*cond_A,"{}: cond_B = {}, cond_A = {}",eq.id(),cond_B,cond_A);
+
+        let x = cholesky_solve_equilibrated(&A,&b,0f64,eq.as_ref()).unwrap();
+        assert!((&b-&A*&x).norm() < 1e-8*(1f64+A.norm()*x.norm()),"{}",eq.id());
+        let y = qr_solve_equilibrated(&A,&b,0f64,eq.as_ref()).unwrap();
+        assert!((&x-&y).norm() < 1e-6*x.norm(),"{}",eq.id());
+    }
+    let (_,B) = SinkhornKnopp::new(1e-6,500).equilibrate(&A);
+    assert!(B.abs().row_iter().all(|r| (r.sum()-1f64).abs() < 1e-5));
+    let (_,B) = RuizConvergent::new(1e-6,200).equilibrate(&A);
+    assert!(B.row_iter().all(|r| (r.amax()-1f64).abs() < 1e-5));
+
+    // all rounds of ruiz_equilibration are applied, also the ||.||_2 rounds after the ||.||_oo ones
+    let (_,B) = ruiz_equilibration(&A,30,0);
+    assert!(B.row_iter().all(|r| (r.amax()-1f64).abs() < 1e-6));
+    let (_,B) = ruiz_equilibration(&A,5,30);
+    assert!(B.row_iter().all(|r| (r.norm()-1f64).abs() < 1e-3));
+}
+
+
+#[test]
+fn test_cholesky_update() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(71);
+    let n = 50usize;
+    let A = random_psd_matrix(n,0.1f64,10f64,&mut rng);
+    let v = random_vector(n,-1f64,1f64,&mut rng);
+    let L = A.clone().cholesky().unwrap().l();
+
+    let A_plus = &A+&v*v.transpose();
+    let L_plus = cholesky_update(&L,&v,1f64).unwrap();
+    assert!((&L_plus*L_plus.transpose()-&A_plus).amax() < 1e-12*A_plus.amax());
+    assert!((&L_plus-A_plus.clone().cholesky().unwrap().l()).amax() < 1e-10);
+
+    // downdate restores the original factor
+    let L_minus = cholesky_update(&L_plus,&v,-1f64).unwrap();
+    assert!((&L_minus-&L).amax() < 1e-10);
+
+    // downdate to an indefinite matrix fails
+    let w = 10f64*DVec::from_fn(n,|i,_| if i==0 { 1f64 } else { 0f64 });
+    assert!(cholesky_update(&L,&w,-1f64).is_err());
+}
+
+
+#[test]
+fn test_structured_solve() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(73);
+    let n = 40usize;
+    let k = 3usize;
+    let d = random_vector(n,0.5f64,2f64,&mut rng);
+    let U = random_matrix(n,k,-1f64,1f64,&mut rng);
+    let C = random_psd_matrix(k,1f64,20f64,&mut rng);
+    let blocks = vec![
+        random_psd_matrix(10,0.1f64,10f64,&mut rng),
+        random_psd_matrix(25,0.1f64,10f64,&mut rng),
+        random_psd_matrix(5,0.1f64,10f64,&mut rng)
+    ];
+    let matrices = vec![
+        StructuredMatrix::Diagonal(d.clone()),
+        StructuredMatrix::DiagonalPlusLowRank{ d: d.clone(), U: U.clone(), C: C.clone() },
+        StructuredMatrix::BlockDiagonal(blocks),
+        StructuredMatrix::Dense(random_psd_matrix(n,0.1f64,10f64,&mut rng)),
+    ];
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+    let x = random_vector(n,-1f64,1f64,&mut rng);
+    for M in matrices.iter() {
+
+        let M_dense = M.to_dense();
+        assert_eq!(M.dim(),n);
+        assert!((M.mul_vec(&x)-&M_dense*&x).norm() < 1e-12*M_dense.norm());
+        assert!((M.diagonal()-M_dense.diagonal()).norm() < 1e-12*M_dense.norm());
+        for &l in [0f64, 1e-3].iter() {
+            let y = M.solve_regularized(&b,l).unwrap();
+            let z = cholesky_solve_regularized(&M_dense,&b,l).unwrap();
+            assert!((&y-&z).norm() < 1e-9*(1f64+z.norm()));
+        }
+    }
+
+    // indefinite matrices are rejected
+    let mut e = d.clone();
+    e[7] = -1f64;
+    assert!(StructuredMatrix::Diagonal(e.clone()).solve_regularized(&b,0f64).is_err());
+    let M = StructuredMatrix::DiagonalPlusLowRank{ d: e, U: U.clone(), C: C.clone() };
+    assert!(M.to_dense().cholesky().is_none() && M.solve_regularized(&b,0f64).is_err());
+    // positive diagonal, indefinite low rank term with an indefinite sum
+    let M = StructuredMatrix::DiagonalPlusLowRank{ d: d.clone(), U: U.clone(), C: -100f64*&C };
+    assert!(M.to_dense().cholesky().is_none() && M.solve_regularized(&b,0f64).is_err());
+    // indefinite low rank term with a positive definite sum
+    let M = StructuredMatrix::DiagonalPlusLowRank{ d: d.clone(), U: U.clone(), C: -1e-3f64*&C };
+    assert!(M.to_dense().cholesky().is_some() && M.solve_regularized(&b,0f64).is_ok());
+}
+
+
+#[test]
+fn test_multiple_triangular_solves() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(79);
+    let n = 150usize;   // several blocks
+    let m = 7usize;
+    let A = random_psd_matrix(n,0.1f64,10f64,&mut rng);
+    let L: DMat = A.clone().cholesky().unwrap().l();
+    let U: DMat = L.transpose();
+    let Y = random_matrix(n,m,-1f64,1f64,&mut rng);
+    let l = 0.01f64;
+    let I = DMat::identity(n,n);
+
+    let X = forward_solve_multiple(&L,&Y,l).unwrap();
+    assert!(((&L+l*&I)*&X-&Y).amax() < 1e-10);
+    let X = back_solve_multiple(&U,&Y,l).unwrap();
+    assert!(((&U+l*&I)*&X-&Y).amax() < 1e-10);
+
+    // columns agree with the single right hand side solves
+    let mut Z = Y.clone();
+    forward_solve_multiple_in_place(&L,&mut Z,0f64).unwrap();
+    back_solve_multiple_in_place(&U,&mut Z,0f64).unwrap();
+    for c in 0..m {
+        let y: DVec = Y.column(c).into_owned();
+        let mut x = y.clone();
+        forward_solve_in_place(&L,&mut x,0f64).unwrap();
+        back_solve_in_place(&U,&mut x,0f64).unwrap();
+        assert!((&x-Z.column(c)).amax() < 1e-10);
+        assert!((&A*&x-&y).norm() < 1e-10*A.norm());
+    }
+
+    let mut S = L.clone();
+    S[(3,3)] = 0f64;
+    assert!(forward_solve_multiple(&S,&Y,0f64).is_err());
+}
+
+
+#[test]
+fn test_generic_scalar() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(83);
+    let n = 50usize;
+
+    // single precision
+    let A: DMatrix<f32> = random_psd_matrix(n,0.1f32,10f32,&mut rng);
+    let b: DVector<f32> = random_vector(n,-1f32,1f32,&mut rng);
+    let x = cholesky_solve_regularized(&A,&b,0f32).unwrap();
+    assert!((&b-&A*&x).norm() < 1e-4*A.norm()*x.norm());
+    let x = qr_solve(&A,&b,0f32).unwrap();
+    assert!((&b-&A*&x).norm() < 1e-4*A.norm()*x.norm());
+    let L = A.clone().cholesky().unwrap().l();
+    let w = forward_solve(&L,&b,0f32).unwrap();
+    let x = back_solve(&L.transpose(),&w,0f32).unwrap();
+    assert!((&b-&A*&x).norm() < 1e-4*A.norm()*x.norm());
+
+    // the f64 solution agrees with the f32 one to single precision
+    let A64: DMat = A.map(|a| a as f64);
+    let b64: DVec = b.map(|a| a as f64);
+    let x64 = cholesky_solve_regularized(&A64,&b64,0f64).unwrap();
+    assert!((&x64-x.map(|a| a as f64)).norm() < 1e-3*x64.norm());
+    let (y,rank) = svd_solve(&A,&b,1e-6f32).unwrap();
+    assert!(rank==n && (&y-&x).norm() < 1e-3*x.norm());
+
+    // selectable equilibration and condition estimates
+    let y = cholesky_solve_equilibrated(&A,&b,0f32,&SinkhornKnopp::new(1e-3,50)).unwrap();
+    assert!((&y-&x).norm() < 1e-3*x.norm());
+    let (y,method,cond) = auto_solve(&A,&b,0f32).unwrap();
+    assert!(method=="Cholesky" && cond < 1e3 && (&y-&x).norm() < 1e-3*x.norm());
+    let (l_min,l_max) = lanczos_extreme_eigenvalues(&A,n);
+    assert!((l_min-0.1).abs() < 1e-3 && (l_max-10.0).abs() < 1e-2);
+}
+
+
+#[test]
+fn test_mixed_precision_cholesky_solve() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(89);
+    let n = 200usize;
+    let b = random_vector(n,-1f64,1f64,&mut rng);
+
+    // moderately conditioned: f32 factorization refined to f64 accuracy
+    let A = random_psd_matrix(n,0.01f64,100f64,&mut rng);
+    let (x,method,cond) = mixed_precision_cholesky_solve(&A,&b,0f64).unwrap();
+    assert!(method=="mixed" && cond <= COND_MAX_MIXED_PRECISION);
+    let x64 = cholesky_solve_regularized(&A,&b,0f64).unwrap();
+    assert!((&b-&A*&x).norm() < 1e-12*A.norm()*x.norm());
+    assert!((&x-&x64).norm() < 1e-10*x64.norm());
+
+    // ill conditioned: falls back to the f64 factorization
+    let A = random_psd_matrix(n,1e-5f64,1e5f64,&mut rng);
+    let (x,method,cond) = mixed_precision_cholesky_solve(&A,&b,0f64).unwrap();
+    assert!(method=="double" && cond > COND_MAX_MIXED_PRECISION);
+    assert!((&b-&A*&x).norm() < 1e-8*A.norm()*x.norm());
+}
@@ -0,0 +1,30 @@
+use convopt::optimization::*;
+
+#[test]
+fn test_brent_search() {
+
+    // minimizer t=0.3
+    let f = |t:f64| (t-0.3f64).exp()-t;
+    let eps = 1e-8;
+    let brent = BrentSearch::new(eps,100).minimize(&f,0f64,1f64);
+    let golden = GoldenSearch::new(eps).minimize(&f,0f64,1f64);
+
+    assert!(brent.converged);
+    assert!((brent.t-0.3).abs() < 1e-6);
+    assert!(brent.evaluations < golden.evaluations);
+
+    // iteration limit
+    let res = brent_search(&f,0f64,1f64,eps,3);
+    assert!(!res.converged && res.evaluations == 4);
+}
+
+
+#[test]
+fn test_single_precision_search() {
+
+    let f = |t:f32| (t-0.3f32).exp()-t;
+    let brent: LineSearchResult<f32> = BrentSearch::new(1e-4,100).minimize(&f,0f32,1f32);
+    assert!(brent.converged && (brent.t-0.3).abs() < 1e-3);
+    let res = backtracking_search(&f,f(0f32),-0.26f32,0.25f32,0.5f32,30);
+    assert!(res.converged && res.f_t < f(0f32));
+}
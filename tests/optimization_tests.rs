@@ -0,0 +1,385 @@
+use convopt::{
+    DVec, DMat,
+    error::{ConvOptError, ErrKind},
+    optimization::*,
+    matrix_utils::two_sided_ruiz_equilibration,
+    equation::*,
+    test_problems::{Maxent, Rosenbrook, LeastSquares, MaxOfAffine}
+};
+
+
+#[test]
+fn test_damped_newton() {
+
+    let dim = 5usize;
+    let min_prob = Maxent::new(dim);
+    let res = damped_newton(&min_prob,0.25,0.5,1e-10,50).unwrap();
+    assert!(res.half_decrement_squared <= 1e-10);
+    assert!(min_prob.gradient(&res.x).norm() < 1e-4);
+    // all coordinates are equal by symmetry
+    assert!((res.x.max()-res.x.min()) < 1e-6);
+
+    let min_prob = Rosenbrook::new(1f64,10f64);
+    let res = damped_newton(&min_prob,0.25,0.5,1e-12,50).unwrap();
+    assert!((&res.x-DVec::from_row_slice(&[1f64,-1f64])).norm() < 1e-5);
+
+    // indefinite Hessian
+    let min_prob = Saddle{ G: WholeSpace::new(2) };
+    assert!(matches!(
+        damped_newton(&min_prob,0.25,0.5,1e-12,50),
+        Err(ConvOptError{ kind: ErrKind::CholeskyFailure(_) })
+    ));
+}
+
+
+/// f(x) = (x_0²-x_1²)/2, the Hessian is indefinite.
+struct Saddle {
+    G: WholeSpace,
+}
+impl MinProblem for Saddle {
+
+    fn id(&self) -> String { String::from("SaddleProblem") }
+    fn dim(&self) -> usize { 2 }
+    fn start_point(&self) -> DVec { DVec::repeat(2,1f64) }
+    fn objective_fn(&self, x: &DVec) -> f64 { 0.5*(x[0]*x[0]-x[1]*x[1]) }
+    fn gradient(&self, x: &DVec) -> DVec { DVec::from_row_slice(&[x[0],-x[1]]) }
+    fn hessian(&self, _x: &DVec) -> DMat { DMat::from_row_slice(2,2,&[1f64,0f64,0f64,-1f64]) }
+    fn domain(&self) -> &dyn Region { &self.G }
+}
+
+
+#[test]
+fn test_first_order_methods() {
+
+    let min_prob = Rosenbrook::new(1f64,10f64);
+    let x_opt = DVec::from_row_slice(&[1f64,-1f64]);
+    let eps = 1e-6;
+
+    let res_gd = gradient_descent(&min_prob,0.25,0.5,eps,100000).unwrap();
+    assert!((&res_gd.x-&x_opt).norm() < 1e-4);
+    let res_nesterov = nesterov_descent(&min_prob,eps,10000).unwrap();
+    assert!((&res_nesterov.x-&x_opt).norm() < 1e-4);
+    let res_fista = fista(&min_prob,eps,100000).unwrap();
+    assert!((&res_fista.x-&x_opt).norm() < 1e-4);
+    assert!(res_nesterov.iterations < res_gd.iterations);
+
+    let min_prob = Maxent::new(20);
+    let res = nesterov_descent(&min_prob,eps,10000).unwrap();
+    assert!(min_prob.gradient(&res.x).norm() < eps*20f64.sqrt());
+}
+
+
+#[test]
+fn test_proximal_gradient() {
+
+    // lasso with diagonal A: minimizer x_i = soft_threshold(a_i*b_i,lambda)/a_i²
+    let n = 10usize;
+    let a = DVec::from_fn(n,|i,_| 1f64+(i as f64)/2f64);
+    let b = DVec::from_fn(n,|i,_| if i%2==0 { 2f64 } else { -0.1f64 });
+    let A = DMat::from_diagonal(&a);
+    let lambda = 0.5f64;
+    let x_opt = soft_threshold(&a.component_mul(&b),lambda).component_div(&a.component_mul(&a));
+
+    let prob = Composite::new(LeastSquares::new(A,b), L1Norm::new(lambda));
+    let res = proximal_gradient(&prob,1e-10,10000).unwrap();
+    assert!((&res.x-&x_opt).norm() < 1e-8);
+    let res = accelerated_proximal_gradient(&prob,1e-10,10000).unwrap();
+    assert!((&res.x-&x_opt).norm() < 1e-8);
+
+    // projection onto the simplex
+    let v = DVec::from_row_slice(&[0.5,1.5,-1.0,0.2]);
+    let p = project_simplex(&v,1f64);
+    assert!((&p-DVec::from_row_slice(&[0.0,1.0,0.0,0.0])).norm() < 1e-12);
+}
+
+
+#[test]
+fn test_admm_consensus() {
+
+    // solve_min_problem logs to results/
+    std::fs::create_dir_all("results").unwrap();
+
+    // min ||x-a||²/2 + ||z-b||²/2 subject to x=z, solution x=z=(a+b)/2
+    let n = 3usize;
+    let a = DVec::from_row_slice(&[1f64,2f64,3f64]);
+    let b = DVec::from_row_slice(&[-1f64,0f64,5f64]);
+    let f = LeastSquares::new(DMat::identity(n,n),a.clone());
+    let g = LeastSquares::new(DMat::identity(n,n),b.clone());
+    let prob = AdmmProblem::new(
+        &f,&g,DMat::identity(n,n),-DMat::identity(n,n),DVec::repeat(n,0f64)
+    );
+    let solver = NewtonSubproblemSolver::new(1e-10,100);
+    let res = admm(&prob,Some(&solver),Some(&solver),&AdmmOptions::default()).unwrap();
+    let x_opt = 0.5f64*(&a+&b);
+    assert!((&res.x-&x_opt).norm() < 1e-4);
+    assert!((&res.z-&x_opt).norm() < 1e-4);
+
+    // default subproblem solvers
+    let res = admm(&prob,None,None,&AdmmOptions::default()).unwrap();
+    assert!((&res.x-&x_opt).norm() < 1e-4);
+    assert!((&res.z-&x_opt).norm() < 1e-4);
+}
+
+
+#[test]
+fn test_coordinate_descent() {
+
+    let min_prob = Maxent::new(10);
+    let blocks = singleton_blocks(10);
+    let eps = 1e-6;
+
+    let res = coordinate_descent(
+        &min_prob,&blocks,&CoordinateOrder::Cyclic,&CoordinateUpdate::DiagonalNewton,eps,1000
+    ).unwrap();
+    assert!((res.x.max()-res.x.min()) < 1e-5);
+
+    let exact = CoordinateUpdate::Exact{ line_search: Box::new(BrentSearch::new(1e-10,100)), radius: 1f64 };
+    let res_random = coordinate_descent(
+        &min_prob,&blocks,&CoordinateOrder::Randomized(37),&exact,eps,1000
+    ).unwrap();
+    assert!((&res_random.x-&res.x).norm() < 1e-4);
+
+    // blocks of two coordinates
+    let blocks: Vec<Vec<usize>> = (0..5).map(|k| vec![2*k,2*k+1]).collect();
+    let res_blocks = coordinate_descent(
+        &min_prob,&blocks,&CoordinateOrder::Randomized(37),&CoordinateUpdate::DiagonalNewton,eps,1000
+    ).unwrap();
+    assert!((&res_blocks.x-&res.x).norm() < 1e-4);
+}
+
+
+#[test]
+fn test_nonsmooth_methods() {
+
+    // f(x) = max(|x_0-1|,|x_1+1|) as a maximum of 4 affine functions, minimum 0 at (1,-1)
+    let A = DMat::from_row_slice(4,2,&[
+        1.0, 0.0,
+        -1.0, 0.0,
+        0.0, 1.0,
+        0.0, -1.0
+    ]);
+    let b = DVec::from_row_slice(&[-1.0,1.0,1.0,-1.0]);
+    let prob = MaxOfAffine::new(A,b);
+    let x_opt = DVec::from_row_slice(&[1f64,-1f64]);
+
+    let res = subgradient_method(&prob,&SubgradientStep::Polyak(0f64),1e-8,10000);
+    assert!(res.converged && res.f_best <= 1e-8);
+    let res = subgradient_method(&prob,&SubgradientStep::Diminishing(1f64),1e-8,10000);
+    assert!(!res.converged && res.iterations == 10000 && res.f_best <= 1e-2);
+
+    let res = proximal_bundle(&prob,1f64,0.1,1e-10,1,20);
+    assert!(!res.converged && res.iterations == 1);
+    let res = proximal_bundle(&prob,1f64,0.1,1e-10,200,20);
+    assert!(res.converged);
+    assert!((&res.x-&x_opt).norm() < 1e-6);
+    assert!(res.lower_bound(1f64) <= 0f64 && res.lower_bound(1f64) > -1e-4);
+}
+
+
+/// f(x) = ||x||² with a wrong gradient: at x=0 no step can decrease f,
+/// but the gradient never vanishes.
+struct WrongGradient {
+    G: WholeSpace,
+}
+impl MinProblem for WrongGradient {
+
+    fn id(&self) -> String { String::from("WrongGradientProblem") }
+    fn dim(&self) -> usize { 2 }
+    fn start_point(&self) -> DVec { DVec::repeat(2,0f64) }
+    fn objective_fn(&self, x: &DVec) -> f64 { x.norm_squared() }
+    fn gradient(&self, x: &DVec) -> DVec { x*2f64+DVec::repeat(2,1f64) }
+    fn hessian(&self, _x: &DVec) -> DMat { 2f64*DMat::identity(2,2) }
+    fn domain(&self) -> &dyn Region { &self.G }
+}
+
+
+#[test]
+fn test_stagnation_detection() {
+
+    std::fs::create_dir_all("results").unwrap();
+    let log_file = "results/WrongGradientProblem.log";
+    let _ = std::fs::remove_file(log_file);
+    let min_prob = WrongGradient{ G: WholeSpace::new(2) };
+    let line_search = GoldenSearch::new(0.1);
+    match solve_min_problem(&min_prob,&line_search,1e-6,1000) {
+        Err(ConvOptError{ kind: ErrKind::Stalled(diagnostics) }) => {
+            assert!(diagnostics.iteration < 20);
+            assert_eq!(diagnostics.recovery_attempts.len(),3);
+        },
+        _ => assert!(false,"solver did not report stagnation")
+    }
+    // the steps at the stalled iterate reuse the decomposition of its Hessian
+    let log = std::fs::read_to_string(log_file).unwrap();
+    assert!(log.matches("linear solver: Cholesky").count() == 1);
+    assert!(log.contains("linear solver: RegularizedSolver"));
+
+    // newton_step with a RegularizedSolver of the Hessian takes the same step
+    let x0 = DVec::from_row_slice(&[1f64,2f64]);
+    let solver = RegularizedSolver::new(&min_prob.hessian(&x0));
+    for &lambda in [0f64, 0.1].iter() {
+        let step = newton_step(&x0,&min_prob,1f64,lambda,&line_search,None).unwrap();
+        let reused = newton_step_with_regularized_solver(
+            &x0,&min_prob,1f64,lambda,&line_search,None,Some(&solver)
+        ).unwrap();
+        assert_eq!(reused.linear_solver,"RegularizedSolver");
+        assert!((&step.next_point-&reused.next_point).norm() < 1e-10);
+    }
+}
+
+
+#[test]
+fn test_sqp() {
+
+    // min ||x-(2,2)||²/2 subject to x_0²+x_1² <= 2 and x_0 <= 5,
+    // solution (1,1) with multipliers (1/2,0)
+    let n = 2usize;
+    let min_prob = LeastSquares::new(DMat::identity(n,n),DVec::repeat(n,2f64));
+    let mut constraints = ConstraintSet::new(String::from("disk"),n);
+    constraints.add_constraint(Box::new(QuadraticInequalityConstraint::new(
+        String::from("x'x <= 2"),DVec::repeat(n,0f64),2f64*DMat::identity(n,n),2f64
+    )));
+    constraints.add_constraint(Box::new(LinearInequalityConstraint::new(
+        String::from("x_0 <= 5"),DVec::from_row_slice(&[1f64,0f64]),5f64
+    )));
+    let res = sqp(&min_prob,&constraints,1e-10,100).unwrap();
+    assert!((&res.x-DVec::repeat(n,1f64)).norm() < 1e-8);
+    assert!((&res.multipliers-DVec::from_row_slice(&[0.5,0.0])).norm() < 1e-8);
+}
+
+
+#[test]
+fn test_constraint_scaling() {
+
+    // min ||x-(2,2,2)||²/2 subject to 1e3*(x_0+x_1) <= 1e3 and 1e-3*x_2 <= -1e-3,
+    // solution (0.5,0.5,-1) with multipliers (1.5e-3,3e3)
+    let n = 3usize;
+    let t = DVec::repeat(n,2f64);
+    let mut constraints = ConstraintSet::new(String::from("badly scaled"),n);
+    constraints.add_constraint(Box::new(LinearInequalityConstraint::new(
+        String::from("x_0+x_1 <= 1"),DVec::from_row_slice(&[1e3,1e3,0f64]),1e3
+    )));
+    constraints.add_constraint(Box::new(LinearInequalityConstraint::new(
+        String::from("x_2 <= -1"),DVec::from_row_slice(&[0f64,0f64,1e-3]),-1e-3
+    )));
+    let scaled = constraints.equilibrate(10).unwrap();
+    let (B,_) = scaled.constraints.linear_constraint_matrix().unwrap();
+    let B = B.to_dense();
+    assert!(B.row_iter().all(|r| (r.amax()-1f64).abs() < 1e-6));
+
+    // objective in the scaled variables u: f(D_c u)
+    let min_prob = LeastSquares::new(DMat::from_diagonal(&scaled.d_c),t);
+    let res = sqp(&min_prob,&scaled.constraints,1e-10,100).unwrap();
+    let x = scaled.unscale_primal(&res.x);
+    let y = scaled.unscale_dual(&res.multipliers);
+    assert!((&x-DVec::from_row_slice(&[0.5,0.5,-1.0])).norm() < 1e-7);
+    assert!((y[0]-1.5e-3).abs() < 1e-9 && (y[1]-3e3).abs() < 1e-4);
+    assert!((scaled.scale_primal(&x)-&res.x).norm() < 1e-12);
+
+    // dense two-sided equilibration of a rectangular matrix
+    let A = DMat::from_row_slice(2,3,&[1e4,2f64,0f64,3e-2,0f64,5e6]);
+    let (d_r,d_c,B) = two_sided_ruiz_equilibration(&A,20);
+    assert!(B.row_iter().all(|r| (r.amax()-1f64).abs() < 1e-3));
+    assert!(B.column_iter().all(|c| (c.amax()-1f64).abs() < 1e-3));
+    assert!((DMat::from_diagonal(&d_r)*&A*DMat::from_diagonal(&d_c)-&B).amax() < 1e-12);
+}
+
+
+#[test]
+fn test_linear_solver_options() {
+
+    std::fs::create_dir_all("results").unwrap();
+
+    // the solvers agree on a positive definite system, on an indefinite one the default chain
+    // skips Cholesky and the positive definite LDLT
+    let H = DMat::from_row_slice(3,3,&[4.0,1.0,0.0, 1.0,3.0,1.0, 0.0,1.0,2.0]);
+    let b = DVec::from_row_slice(&[1.0,2.0,3.0]);
+    let x = FallbackSolver::default().solve(&H,&b,0f64).unwrap();
+    assert_eq!(x.solver,"Cholesky");
+    for solver in [&QRSolver{} as &dyn LinearSolver, &LDLTSolver::new(true), &SVDSolver::new(1e-12)].iter() {
+        assert!((&solver.solve(&H,&b,0f64).unwrap().x-&x.x).norm() < 1e-10);
+    }
+    let S = DMat::from_row_slice(2,2,&[1.0,0.0, 0.0,-1.0]);
+    let y = FallbackSolver::default().solve(&S,&DVec::repeat(2,1f64),0f64).unwrap();
+    assert_eq!(y.solver,"SVD");
+    assert!(LDLTSolver::new(true).solve(&S,&DVec::repeat(2,1f64),0f64).is_err());
+    let fallback = FallbackSolver::new(
+        vec![Box::new(CholeskySolver{}),Box::new(LDLTSolver::new(false))]
+    );
+    assert_eq!(fallback.solve(&S,&DVec::repeat(2,1f64),0f64).unwrap().solver,"LDLT");
+
+    // newton_direction reports the method it actually used
+    let (_,_,_,method) = newton_direction(&(-&b),&H,0f64).unwrap();
+    assert_eq!(method,"Cholesky");
+    let (_,shift,cond,method) = newton_direction(&DVec::repeat(2,-1f64),&S,0f64).unwrap();
+    assert!(method == "modified Cholesky" && shift > 0f64 && cond.is_infinite());
+
+    // Newton solver with the fallback chain
+    let min_prob = Rosenbrook::new(1f64,10f64);
+    let options = NewtonOptions{
+        eps: 1e-8, max_iter: 200, linear_solver: Some(Box::new(FallbackSolver::default()))
+    };
+    let x = solve_min_problem_with_options(&min_prob,&GoldenSearch::new(0.1),&options).unwrap();
+    assert!((&x-DVec::from_row_slice(&[1f64,-1f64])).norm() < 1e-5);
+}
+
+
+/// f(x) = (x_0+x_1-2)², the Hessian is singular (rank one).
+struct FlatValley {
+    G: WholeSpace,
+}
+impl MinProblem for FlatValley {
+
+    fn id(&self) -> String { String::from("FlatValleyProblem") }
+    fn dim(&self) -> usize { 2 }
+    fn start_point(&self) -> DVec { DVec::repeat(2,0f64) }
+    fn objective_fn(&self, x: &DVec) -> f64 { (x[0]+x[1]-2f64).powi(2) }
+    fn gradient(&self, x: &DVec) -> DVec { DVec::repeat(2,2f64*(x[0]+x[1]-2f64)) }
+    fn hessian(&self, _x: &DVec) -> DMat { DMat::repeat(2,2,2f64) }
+    fn domain(&self) -> &dyn Region { &self.G }
+}
+
+
+#[test]
+fn test_svd_solve() {
+
+    // singular and rectangular least squares problems
+    let H = DMat::from_row_slice(2,2,&[2.0,0.0, 0.0,0.0]);
+    let (x,rank) = svd_solve(&H,&DVec::from_row_slice(&[2.0,1.0]),1e-12).unwrap();
+    assert_eq!(rank,1);
+    assert!((&x-DVec::from_row_slice(&[1.0,0.0])).norm() < 1e-12);
+    let A = DMat::from_row_slice(3,2,&[1.0,1.0, 1.0,1.0, 2.0,2.0]);
+    let (x,rank) = svd_solve(&A,&DVec::from_row_slice(&[1.0,1.0,2.0]),1e-12).unwrap();
+    assert_eq!(rank,1);
+    assert!((&x-DVec::from_row_slice(&[0.5,0.5])).norm() < 1e-12);
+
+    // Newton step in a flat valley: minimum norm step to the valley floor, rank reported
+    let min_prob = FlatValley{ G: WholeSpace::new(2) };
+    let x0 = min_prob.start_point();
+    let step = newton_step(
+        &x0,&min_prob,10f64,0f64,&GoldenSearch::new(0.1),Some(&SVDSolver::new(1e-12))
+    ).unwrap();
+    assert_eq!(step.linear_solver,"SVD");
+    assert_eq!(step.hessian_rank,Some(1));
+    assert!((&step.next_point-DVec::from_row_slice(&[1.0,1.0])).norm() < 1e-6);
+}
+
+
+#[test]
+fn test_structured_newton_step() {
+
+    // the Maxent Hessian diag(1/x)+20*11' is solved with Woodbury
+    let min_prob = Maxent::new(20);
+    let x0 = min_prob.start_point();
+    let step = newton_step(&x0,&min_prob,1f64,0f64,&GoldenSearch::new(0.1),None).unwrap();
+    assert_eq!(step.linear_solver,"structured");
+    assert!(step.objF_next_point < min_prob.objective_fn(&x0));
+
+    // same (regularized) Newton direction as the dense solve
+    let g = min_prob.gradient(&x0);
+    let H = min_prob.structured_hessian(&x0).unwrap();
+    for &lambda in [0f64, 1e-3, 0.5].iter() {
+        let p = H.solve_regularized(&(-&g),lambda).unwrap();
+        let (q,_,_,_) = newton_direction(&g,&min_prob.hessian(&x0),lambda).unwrap();
+        assert!((&p-&q).norm() < 1e-8*q.norm());
+    }
+}
@@ -0,0 +1,173 @@
+use convopt::{
+    DVec, DMat,
+    sparse::*,
+    optimization::*,
+    equation::*,
+    error::{ConvOptError, ErrKind},
+    matrix_utils::random_vector
+};
+use rand_xoshiro::{Xoshiro256PlusPlus, rand_core::SeedableRng};
+
+
+/// 5-point Laplacian on a k x k grid plus s*I.
+fn grid_laplacian(k: usize, s: f64) -> CscMatrix {
+
+    let mut triplets = Vec::new();
+    for r in 0..k {
+        for c in 0..k {
+            let i = r*k+c;
+            triplets.push((i,i,4f64+s));
+            if r+1 < k { triplets.push((i,i+k,-1f64)); triplets.push((i+k,i,-1f64)); }
+            if c+1 < k { triplets.push((i,i+1,-1f64)); triplets.push((i+1,i,-1f64)); }
+        }
+    }
+    CscMatrix::from_triplets(k*k,k*k,&triplets)
+}
+
+
+#[test]
+fn test_sparse_cholesky() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(37);
+    let k = 15usize;
+    let n = k*k;
+    let A = grid_laplacian(k,0.1);
+    let b = random_vector(n,0f64,1f64,&mut rng);
+
+    let x = sparse_cholesky_solve(&A,&b,0f64).unwrap();
+    let residual = &b-A.mul_vec(&x);
+    assert!(residual.norm() < 1e-12*b.norm());
+    assert!((A.to_dense()*&x-&b).norm() < 1e-12*b.norm());
+
+    // approximate minimum degree ordering produces less fill than the natural ordering
+    let mut perm = amd_ordering(&A);
+    perm.sort_unstable();
+    assert_eq!(perm,(0..n).collect::<Vec<usize>>());
+    let symbolic = SymbolicCholesky::analyze(&A);
+    let natural = SymbolicCholesky::analyze_with_ordering(&A,(0..n).collect());
+    assert!(symbolic.nnz_l() < natural.nnz_l());
+
+    // reuse of the symbolic factorization for A+lI
+    let l = 2.5f64;
+    let x = symbolic.factor(&A,l).unwrap().solve(&b);
+    let residual = &b-A.mul_vec(&x)-l*&x;
+    assert!(residual.norm() < 1e-12*b.norm());
+
+    // indefinite matrix
+    assert!(symbolic.factor(&A,-5f64).is_err());
+
+    // arrow matrix: the dense row/column must be eliminated among the last two to avoid fill
+    let m = 50usize;
+    let mut triplets: Vec<(usize,usize,f64)> = (0..m).map(|i| (i,i,m as f64)).collect();
+    for i in 1..m { triplets.push((0,i,1f64)); triplets.push((i,0,1f64)); }
+    let arrow = CscMatrix::from_triplets(m,m,&triplets);
+    assert!(amd_ordering(&arrow)[m-2..].contains(&0));
+    assert_eq!(SymbolicCholesky::analyze(&arrow).nnz_l(),2*m-1);
+}
+
+
+/// f(x) = sum_i x_i*log(x_i) + (1/2)sum_i (x_i-x_{i+1})² - sum_i x_i on x>0,
+/// tridiagonal Hessian.
+struct ChainProblem {
+    dim: usize,
+    sparse: bool,
+    G: AllPositive,
+}
+impl MinProblem for ChainProblem {
+
+    fn id(&self) -> String { String::from("ChainProblem") }
+    fn dim(&self) -> usize { self.dim }
+    fn start_point(&self) -> DVec { DVec::from_fn(self.dim,|i,_| 0.5+(i as f64)/(self.dim as f64)) }
+    fn objective_fn(&self, x: &DVec) -> f64 {
+        let n = self.dim;
+        x.map(|u| u*u.ln()-u).sum() + (0..n-1).map(|i| 0.5*(x[i]-x[i+1]).powi(2)).sum::<f64>()
+    }
+    fn gradient(&self, x: &DVec) -> DVec {
+        let n = self.dim;
+        DVec::from_fn(n,|i,_| {
+            let mut g = x[i].ln();
+            if i > 0 { g += x[i]-x[i-1]; }
+            if i+1 < n { g += x[i]-x[i+1]; }
+            g
+        })
+    }
+    fn hessian(&self, x: &DVec) -> DMat { self.sparse_hessian_of(x).to_dense() }
+    fn domain(&self) -> &dyn Region { &self.G }
+    fn sparse_hessian(&self, x: &DVec) -> Option<CscMatrix> {
+        if self.sparse { Some(self.sparse_hessian_of(x)) } else { None }
+    }
+}
+impl ChainProblem {
+    fn sparse_hessian_of(&self, x: &DVec) -> CscMatrix {
+        let n = self.dim;
+        let mut triplets = Vec::new();
+        for i in 0..n {
+            let deg = if i == 0 || i+1 == n { 1f64 } else { 2f64 };
+            triplets.push((i,i,1f64/x[i]+deg));
+            if i+1 < n { triplets.push((i,i+1,-1f64)); triplets.push((i+1,i,-1f64)); }
+        }
+        CscMatrix::from_triplets(n,n,&triplets)
+    }
+}
+
+
+#[test]
+fn test_damped_newton_sparse_hessian() {
+
+    let n = 50usize;
+    let sparse = ChainProblem{ dim: n, sparse: true, G: AllPositive::new(n) };
+    let dense = ChainProblem{ dim: n, sparse: false, G: AllPositive::new(n) };
+    let res_sparse = damped_newton(&sparse,0.25,0.5,1e-12,50).unwrap();
+    let res_dense = damped_newton(&dense,0.25,0.5,1e-12,50).unwrap();
+    assert!((&res_sparse.x-&res_dense.x).norm() < 1e-8);
+    // minimizer x_i = 1
+    assert!((&res_sparse.x-DVec::repeat(n,1f64)).norm() < 1e-6);
+}
+
+
+#[test]
+fn test_pcg_solve() {
+
+    let mut rng:Xoshiro256PlusPlus = Xoshiro256PlusPlus::seed_from_u64(59);
+    let k = 30usize;
+    let n = k*k;
+    // badly scaled grid laplacian
+    let s = DVec::from_fn(n,|i,_| 10f64.powi((i%5) as i32-2));
+    let A = grid_laplacian(k,0.01).scale(&s,&s);
+    let b = random_vector(n,0f64,1f64,&mut rng);
+    let tol = 1e-10;
+
+    let plain = pcg_solve(&A,&b,&IdentityPreconditioner{},tol,2000).unwrap();
+    let jacobi = pcg_solve(&A,&b,&DiagonalPreconditioner::jacobi(&A).unwrap(),tol,2000).unwrap();
+    let ruiz = pcg_solve(&A,&b,&DiagonalPreconditioner::sparse_ruiz(&A),tol,2000).unwrap();
+    let ic = IncompleteCholesky::new(&A).unwrap();
+    let ic0 = pcg_solve(&A,&b,&ic,tol,2000).unwrap();
+
+    for result in [&jacobi,&ruiz,&ic0].iter() {
+        assert!(result.converged);
+        assert_eq!(result.residual_norms.len(),result.iterations+1);
+        assert!((&b-A.mul_vec(&result.x)).norm() <= 1e-9*b.norm());
+    }
+    assert!(!plain.converged || plain.iterations > jacobi.iterations);
+    assert!(ic0.iterations < jacobi.iterations);
+
+    // matrix free operator
+    let op = FnOperator::new(n,|x: &DVec| A.mul_vec(x));
+    let mf = pcg_solve(&op,&b,&DiagonalPreconditioner::jacobi(&op).unwrap(),tol,2000).unwrap();
+    assert_eq!(mf.iterations,jacobi.iterations);
+
+    // nonpositive diagonal
+    let indefinite = grid_laplacian(k,-5f64);
+    assert!(matches!(
+        DiagonalPreconditioner::jacobi(&indefinite),
+        Err(ConvOptError{ kind: ErrKind::PreconditionerFailure(_) })
+    ));
+    // indefinite preconditioner
+    let mut s = DVec::repeat(n,1f64);
+    s[0] = -1e6;
+    let scaling = DiagonalPreconditioner{ id: String::from("indefinite"), s };
+    assert!(matches!(
+        pcg_solve(&A,&b,&scaling,tol,2000),
+        Err(ConvOptError{ kind: ErrKind::PreconditionerFailure(_) })
+    ));
+}